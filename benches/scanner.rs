@@ -0,0 +1,44 @@
+//! Scans about 1 MB of Lox source, mixing ASCII and multi-byte text, to
+//! check that scanning takes linear time.
+//!
+//! Run with `cargo bench --bench scanner`.
+
+use rlox::Scanner;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SIZE: usize = 1 << 20;
+const ITERATIONS: u32 = 10;
+
+const CHUNK: &str = "\
+// Übersicht: compute the totals ☕
+var prices = {\"café\": 2.5, \"naïve\": 3, \"变量\": 10};
+var total = 0;
+for (var i = 0; i < len(prices); i = i + 1) {
+    total = total + values(prices)[i];
+}
+print \"Total: ${total} 💶\";
+";
+
+fn main() {
+    let source = CHUNK.repeat(SIZE / CHUNK.len() + 1);
+
+    let mut best = Duration::MAX;
+    let mut tokens = 0;
+    for _ in 0..ITERATIONS {
+        let started = Instant::now();
+        let mut scanner = Scanner::new(black_box(&source));
+        tokens = scanner.scan().count();
+        best = best.min(started.elapsed());
+    }
+
+    let megabytes = source.len() as f64 / (1024. * 1024.);
+    println!(
+        "scanned {:.2} MB into {} tokens in {:?} ({:.1} MB/s, best of {})",
+        megabytes,
+        tokens,
+        best,
+        megabytes / best.as_secs_f64(),
+        ITERATIONS
+    );
+}
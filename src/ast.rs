@@ -1,4 +1,5 @@
 use crate::green::{SyntaxElement, SyntaxNode, SyntaxToken};
+use crate::intern::Symbol;
 use crate::kinds::SyntaxKind;
 
 pub trait AstNode {
@@ -168,9 +169,29 @@ impl VarDeclaration {
     pub fn initializer(&self) -> SyntaxNode {
         self.syntax()
             .children()
-            .find_map(SyntaxElement::into_node)
+            .filter_map(SyntaxElement::into_node)
+            .find(|node| node.kind() != SyntaxKind::Doc)
             .unwrap()
     }
+
+    /// The `///` comments before the declaration without their slashes, one
+    /// line each, if the parser was given them.
+    pub fn doc(&self) -> Option<String> {
+        let doc = self
+            .syntax()
+            .children()
+            .filter_map(SyntaxElement::into_node)
+            .find(|node| node.kind() == SyntaxKind::Doc)?;
+        let lines = doc
+            .children()
+            .filter_map(SyntaxElement::into_token)
+            .map(|comment| {
+                let text = &comment.text()["///".len()..];
+                text.strip_prefix(' ').unwrap_or(text).to_string()
+            })
+            .collect::<Vec<_>>();
+        Some(lines.join("\n"))
+    }
 }
 
 pub struct Identifier(SyntaxNode);
@@ -192,13 +213,15 @@ impl AstNode for Identifier {
 }
 
 impl Identifier {
-    pub fn name(&self) -> String {
-        let token = self
-            .syntax()
+    pub fn token(&self) -> SyntaxToken {
+        self.syntax()
             .children()
             .find_map(SyntaxElement::into_token)
-            .unwrap();
-        token.text().to_string()
+            .unwrap()
+    }
+
+    pub fn name(&self) -> Symbol {
+        self.token().symbol()
     }
 }
 
@@ -300,6 +323,13 @@ impl Logical {
         self.syntax().kind()
     }
 
+    pub fn op(&self) -> SyntaxToken {
+        self.syntax()
+            .children()
+            .find_map(SyntaxElement::into_token)
+            .unwrap()
+    }
+
     pub fn right(&self) -> SyntaxNode {
         self.syntax()
             .children()
@@ -364,14 +394,17 @@ impl AstNode for Assign {
 }
 
 impl Assign {
-    pub fn var_name(&self) -> String {
-        let var = self
-            .syntax()
+    /// The variable or [`Index`] assigned to.
+    pub fn target(&self) -> SyntaxNode {
+        self.syntax()
             .children()
             .find_map(SyntaxElement::into_node)
-            .unwrap();
-        let var_token = var.children().find_map(SyntaxElement::into_token).unwrap();
-        var_token.text().to_string()
+            .unwrap()
+    }
+
+    /// The name of the variable assigned to, if the target is one.
+    pub fn var_name(&self) -> Option<Symbol> {
+        Identifier::cast(self.target()).map(|ident| ident.name())
     }
 
     pub fn value(&self) -> SyntaxNode {
@@ -382,3 +415,404 @@ impl Assign {
             .unwrap()
     }
 }
+
+pub struct Call(SyntaxNode);
+impl AstNode for Call {
+    fn cast(node: SyntaxNode) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if node.kind() == SyntaxKind::Call {
+            Some(Call(node))
+        } else {
+            None
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+}
+
+impl Call {
+    pub fn callee(&self) -> SyntaxNode {
+        self.syntax()
+            .children()
+            .find_map(SyntaxElement::into_node)
+            .unwrap()
+    }
+
+    pub fn arguments(&self) -> impl Iterator<Item = SyntaxNode> + '_ {
+        self.syntax()
+            .children()
+            .filter_map(SyntaxElement::into_node)
+            .skip(1)
+    }
+
+    /// The closing parenthesis of the argument list.
+    pub fn paren(&self) -> SyntaxToken {
+        self.syntax()
+            .children()
+            .find_map(SyntaxElement::into_token)
+            .unwrap()
+    }
+}
+
+pub struct ListLiteral(SyntaxNode);
+impl AstNode for ListLiteral {
+    fn cast(node: SyntaxNode) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if node.kind() == SyntaxKind::ListLiteral {
+            Some(ListLiteral(node))
+        } else {
+            None
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+}
+
+impl ListLiteral {
+    pub fn elements(&self) -> impl Iterator<Item = SyntaxNode> + '_ {
+        self.syntax()
+            .children()
+            .filter_map(SyntaxElement::into_node)
+    }
+}
+
+/// `target[index]`.
+pub struct Index(SyntaxNode);
+impl AstNode for Index {
+    fn cast(node: SyntaxNode) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if node.kind() == SyntaxKind::Index {
+            Some(Index(node))
+        } else {
+            None
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+}
+
+impl Index {
+    pub fn target(&self) -> SyntaxNode {
+        self.syntax()
+            .children()
+            .find_map(SyntaxElement::into_node)
+            .unwrap()
+    }
+
+    pub fn index(&self) -> SyntaxNode {
+        self.syntax()
+            .children()
+            .filter_map(SyntaxElement::into_node)
+            .nth(1)
+            .unwrap()
+    }
+}
+
+pub struct MapLiteral(SyntaxNode);
+impl AstNode for MapLiteral {
+    fn cast(node: SyntaxNode) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if node.kind() == SyntaxKind::MapLiteral {
+            Some(MapLiteral(node))
+        } else {
+            None
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+}
+
+impl MapLiteral {
+    /// The `(key, value)` expressions of each entry.
+    pub fn entries(&self) -> Vec<(SyntaxNode, SyntaxNode)> {
+        let nodes = self
+            .syntax()
+            .children()
+            .filter_map(SyntaxElement::into_node)
+            .collect::<Vec<_>>();
+        nodes
+            .chunks(2)
+            .map(|entry| (entry[0].clone(), entry[1].clone()))
+            .collect()
+    }
+}
+
+/// `for (initializer; condition; increment) body`.
+pub struct For(SyntaxNode);
+impl AstNode for For {
+    fn cast(node: SyntaxNode) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if node.kind() == SyntaxKind::For {
+            Some(For(node))
+        } else {
+            None
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+}
+
+impl For {
+    pub fn initializer(&self) -> Option<SyntaxNode> {
+        self.clause(0)
+    }
+
+    pub fn condition(&self) -> Option<SyntaxNode> {
+        self.clause(1)
+    }
+
+    pub fn increment(&self) -> Option<SyntaxNode> {
+        self.clause(2)
+    }
+
+    pub fn body(&self) -> SyntaxNode {
+        self.syntax()
+            .children()
+            .filter_map(SyntaxElement::into_node)
+            .nth(3)
+            .unwrap()
+    }
+
+    /// An omitted clause is parsed as an empty `Nil` node.
+    fn clause(&self, index: usize) -> Option<SyntaxNode> {
+        self.syntax()
+            .children()
+            .filter_map(SyntaxElement::into_node)
+            .nth(index)
+            .filter(|clause| clause.kind() != SyntaxKind::Nil)
+    }
+}
+
+pub struct Throw(SyntaxNode);
+impl AstNode for Throw {
+    fn cast(node: SyntaxNode) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if node.kind() == SyntaxKind::Throw {
+            Some(Throw(node))
+        } else {
+            None
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+}
+
+impl Throw {
+    pub fn expr(&self) -> SyntaxNode {
+        self.syntax()
+            .children()
+            .find_map(SyntaxElement::into_node)
+            .unwrap()
+    }
+}
+
+/// `try block`, with a `catch` clause, a `finally` clause or both.
+pub struct Try(SyntaxNode);
+impl AstNode for Try {
+    fn cast(node: SyntaxNode) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if node.kind() == SyntaxKind::Try {
+            Some(Try(node))
+        } else {
+            None
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+}
+
+impl Try {
+    pub fn body(&self) -> SyntaxNode {
+        self.syntax()
+            .children()
+            .find_map(SyntaxElement::into_node)
+            .unwrap()
+    }
+
+    pub fn catch_clause(&self) -> Option<Catch> {
+        self.syntax()
+            .children()
+            .filter_map(SyntaxElement::into_node)
+            .find_map(Catch::cast)
+    }
+
+    /// The block of the `finally` clause.
+    pub fn finally_block(&self) -> Option<SyntaxNode> {
+        let finally = self
+            .syntax()
+            .children()
+            .filter_map(SyntaxElement::into_node)
+            .find(|node| node.kind() == SyntaxKind::Finally)?;
+        let block = finally.children().find_map(SyntaxElement::into_node);
+        block
+    }
+}
+
+/// `catch (name) block`.
+pub struct Catch(SyntaxNode);
+impl AstNode for Catch {
+    fn cast(node: SyntaxNode) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if node.kind() == SyntaxKind::Catch {
+            Some(Catch(node))
+        } else {
+            None
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+}
+
+impl Catch {
+    /// The variable the caught error is bound to.
+    pub fn name(&self) -> SyntaxToken {
+        self.syntax()
+            .children()
+            .filter_map(SyntaxElement::into_token)
+            .find(|token| token.kind() == SyntaxKind::Identifier)
+            .unwrap()
+    }
+
+    pub fn body(&self) -> SyntaxNode {
+        self.syntax()
+            .children()
+            .find_map(SyntaxElement::into_node)
+            .unwrap()
+    }
+}
+
+/// `import "path" as name;`
+pub struct Import(SyntaxNode);
+impl AstNode for Import {
+    fn cast(node: SyntaxNode) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if node.kind() == SyntaxKind::Import {
+            Some(Import(node))
+        } else {
+            None
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+}
+
+impl Import {
+    /// The string literal naming the module, with its quotes.
+    pub fn path(&self) -> SyntaxToken {
+        self.syntax()
+            .children()
+            .filter_map(SyntaxElement::into_token)
+            .find(|token| token.kind() == SyntaxKind::String)
+            .unwrap()
+    }
+
+    /// The variable the module is bound to.
+    pub fn name(&self) -> SyntaxToken {
+        self.syntax()
+            .children()
+            .filter_map(SyntaxElement::into_token)
+            .find(|token| token.kind() == SyntaxKind::Identifier)
+            .unwrap()
+    }
+}
+
+/// `export` followed by a declaration.
+pub struct Export(SyntaxNode);
+impl AstNode for Export {
+    fn cast(node: SyntaxNode) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if node.kind() == SyntaxKind::Export {
+            Some(Export(node))
+        } else {
+            None
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+}
+
+impl Export {
+    pub fn declaration(&self) -> VarDeclaration {
+        self.syntax()
+            .children()
+            .find_map(SyntaxElement::into_node)
+            .and_then(VarDeclaration::cast)
+            .unwrap()
+    }
+}
+
+/// `object.name`
+pub struct Get(SyntaxNode);
+impl AstNode for Get {
+    fn cast(node: SyntaxNode) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        if node.kind() == SyntaxKind::Get {
+            Some(Get(node))
+        } else {
+            None
+        }
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+}
+
+impl Get {
+    pub fn object(&self) -> SyntaxNode {
+        self.syntax()
+            .children()
+            .find_map(SyntaxElement::into_node)
+            .unwrap()
+    }
+
+    pub fn name(&self) -> SyntaxToken {
+        self.syntax()
+            .children()
+            .filter_map(SyntaxElement::into_token)
+            .find(|token| token.kind() == SyntaxKind::Identifier)
+            .unwrap()
+    }
+}
@@ -0,0 +1,8 @@
+use rlox::LanguageServer;
+use std::io;
+
+fn main() -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    LanguageServer::new().run(stdin.lock(), stdout.lock())
+}
@@ -0,0 +1,154 @@
+use crate::error::RuntimeError;
+use crate::map::{Map, MapKey};
+use crate::value::Value;
+use std::collections::HashMap;
+
+/// Conversion of a Rust value into a Lox [`Value`].
+pub trait IntoLox {
+    fn into_lox(self) -> Value;
+}
+
+/// Conversion of a Lox [`Value`] into a Rust value, failing if the value has
+/// the wrong type.
+pub trait FromLox: Sized {
+    fn from_lox(value: Value) -> Result<Self, RuntimeError>;
+}
+
+fn type_error(expected: &str, value: &Value) -> RuntimeError {
+    RuntimeError::new(format!(
+        "Expected {} but got {}.",
+        expected,
+        value.type_name()
+    ))
+}
+
+impl IntoLox for Value {
+    fn into_lox(self) -> Value {
+        self
+    }
+}
+
+impl FromLox for Value {
+    fn from_lox(value: Value) -> Result<Value, RuntimeError> {
+        Ok(value)
+    }
+}
+
+impl IntoLox for bool {
+    fn into_lox(self) -> Value {
+        Value::Bool(self)
+    }
+}
+
+impl FromLox for bool {
+    fn from_lox(value: Value) -> Result<bool, RuntimeError> {
+        match value {
+            Value::Bool(b) => Ok(b),
+            _ => Err(type_error("a boolean", &value)),
+        }
+    }
+}
+
+impl IntoLox for f64 {
+    fn into_lox(self) -> Value {
+        Value::Number(self)
+    }
+}
+
+impl FromLox for f64 {
+    fn from_lox(value: Value) -> Result<f64, RuntimeError> {
+        match value {
+            Value::Number(n) => Ok(n),
+            _ => Err(type_error("a number", &value)),
+        }
+    }
+}
+
+impl IntoLox for String {
+    fn into_lox(self) -> Value {
+        Value::String(self.into())
+    }
+}
+
+impl IntoLox for &str {
+    fn into_lox(self) -> Value {
+        Value::String(self.into())
+    }
+}
+
+impl FromLox for String {
+    fn from_lox(value: Value) -> Result<String, RuntimeError> {
+        match value {
+            Value::String(s) => Ok(s.to_string()),
+            _ => Err(type_error("a string", &value)),
+        }
+    }
+}
+
+/// `None` is `nil`.
+impl<T: IntoLox> IntoLox for Option<T> {
+    fn into_lox(self) -> Value {
+        match self {
+            Some(value) => value.into_lox(),
+            None => Value::Nil,
+        }
+    }
+}
+
+impl<T: FromLox> FromLox for Option<T> {
+    fn from_lox(value: Value) -> Result<Option<T>, RuntimeError> {
+        match value {
+            Value::Nil => Ok(None),
+            value => T::from_lox(value).map(Some),
+        }
+    }
+}
+
+/// A new list with the converted elements.
+impl<T: IntoLox> IntoLox for Vec<T> {
+    fn into_lox(self) -> Value {
+        Value::list(self.into_iter().map(IntoLox::into_lox).collect())
+    }
+}
+
+/// Copies the elements out of a list.
+impl<T: FromLox> FromLox for Vec<T> {
+    fn from_lox(value: Value) -> Result<Vec<T>, RuntimeError> {
+        match &value {
+            Value::List(list) => {
+                let elements = list.borrow().clone();
+                elements.into_iter().map(T::from_lox).collect()
+            }
+            _ => Err(type_error("a list", &value)),
+        }
+    }
+}
+
+/// A new map with the converted values, with the keys in sorted order.
+impl<T: IntoLox> IntoLox for HashMap<String, T> {
+    fn into_lox(self) -> Value {
+        let mut entries = self.into_iter().collect::<Vec<_>>();
+        entries.sort_by(|(left, _), (right, _)| left.cmp(right));
+        let mut map = Map::new();
+        for (key, value) in entries {
+            map.insert(MapKey::String(key.into()), value.into_lox());
+        }
+        Value::map(map)
+    }
+}
+
+/// Copies the entries out of a map whose keys are all strings.
+impl<T: FromLox> FromLox for HashMap<String, T> {
+    fn from_lox(value: Value) -> Result<HashMap<String, T>, RuntimeError> {
+        let map = match &value {
+            Value::Map(map) => map.borrow().clone(),
+            _ => return Err(type_error("a map", &value)),
+        };
+        map.iter()
+            .map(|(key, value)| {
+                let key = String::from_lox(key.to_value())?;
+                Ok((key, T::from_lox(value.clone())?))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,307 @@
+use crate::error::{RuntimeError, SyntaxError};
+use crate::line_index::LineIndex;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A message attached to a range of the source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+/// An error or warning together with the source it points at, ready to be
+/// shown to a user by a [`Renderer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    severity: Severity,
+    code: Option<String>,
+    message: String,
+    primary: Option<Label>,
+    secondary: Vec<Label>,
+    notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity,
+            code: None,
+            message: message.into(),
+            primary: None,
+            secondary: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Warning, message)
+    }
+
+    /// Identifies the check that produced the diagnostic, like a lint rule.
+    pub fn with_code(mut self, code: impl Into<String>) -> Diagnostic {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Sets the span the diagnostic is about, underlined with `^`.
+    pub fn with_primary(mut self, span: Range<usize>, message: impl Into<String>) -> Diagnostic {
+        self.primary = Some(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Adds related source, underlined with `-`.
+    pub fn with_label(mut self, span: Range<usize>, message: impl Into<String>) -> Diagnostic {
+        self.secondary.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn primary(&self) -> Option<&Label> {
+        self.primary.as_ref()
+    }
+
+    pub fn labels(&self) -> &[Label] {
+        &self.secondary
+    }
+
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+}
+
+impl From<&SyntaxError> for Diagnostic {
+    fn from(error: &SyntaxError) -> Diagnostic {
+        let diagnostic = Diagnostic::error(error.message()).with_primary(error.span(), "");
+        error
+            .labels()
+            .iter()
+            .fold(diagnostic, |diagnostic, (span, message)| {
+                diagnostic.with_label(span.clone(), message.as_str())
+            })
+    }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+    fn from(error: &RuntimeError) -> Diagnostic {
+        let diagnostic = Diagnostic::error(error.message());
+        match error.span() {
+            Some(span) => diagnostic.with_primary(span, ""),
+            None => diagnostic,
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// Formats diagnostics like rustc: a `file:line:col` header, the source lines
+/// involved, and the labelled spans underlined beneath them.
+pub struct Renderer {
+    colored: bool,
+}
+
+impl Renderer {
+    /// Output without escape codes, for logs and tests.
+    pub fn plain() -> Renderer {
+        Renderer { colored: false }
+    }
+
+    /// Output coloured with ANSI escape codes, for terminals.
+    pub fn ansi() -> Renderer {
+        Renderer { colored: true }
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic, file: &str, source: &str) -> String {
+        let lines = LineIndex::new(source);
+        let (severity, color) = match diagnostic.severity {
+            Severity::Error => ("error", RED),
+            Severity::Warning => ("warning", YELLOW),
+        };
+        let severity = match &diagnostic.code {
+            Some(code) => format!("{}[{}]", severity, code),
+            None => severity.to_string(),
+        };
+        let mut out = format!(
+            "{}{}\n",
+            self.paint(color, &severity),
+            self.paint(BOLD, &format!(": {}", diagnostic.message))
+        );
+
+        let mut labels = Vec::new();
+        labels.extend(diagnostic.primary.iter().map(|label| (label, '^', color)));
+        labels.extend(diagnostic.secondary.iter().map(|label| (label, '-', BLUE)));
+        labels.sort_by_key(|(label, _, _)| label.span.start);
+        let last_line = labels
+            .iter()
+            .map(|(label, _, _)| lines.line(label.span.start))
+            .max()
+            .unwrap_or(0);
+        let width = last_line.to_string().len();
+        let gutter = self.paint(BLUE, &format!("{} |", " ".repeat(width)));
+
+        if let Some(primary) = &diagnostic.primary {
+            let (line, column) = Self::position(&lines, source, primary.span.start);
+            out += &format!(
+                "{}{} {}:{}:{}\n",
+                " ".repeat(width),
+                self.paint(BLUE, "-->"),
+                file,
+                line,
+                column
+            );
+        }
+        if !labels.is_empty() {
+            out += &format!("{}\n", gutter);
+        }
+        let mut previous_line = None;
+        for (label, marker, color) in &labels {
+            let line = lines.line(label.span.start);
+            if previous_line != Some(line) {
+                if previous_line.is_some_and(|previous| line > previous + 1) {
+                    out += &format!("{}\n", self.paint(BLUE, "..."));
+                }
+                let number = format!("{:>width$} |", line, width = width);
+                out += &format!(
+                    "{} {}\n",
+                    self.paint(BLUE, &number),
+                    Self::text(&lines, source, line)
+                );
+                previous_line = Some(line);
+            }
+            let (_, column) = Self::position(&lines, source, label.span.start);
+            let text = Self::text(&lines, source, line);
+            let line_end = lines.line_start(line) + text.len();
+            let end = label.span.end.clamp(label.span.start, line_end);
+            let length = source[label.span.start..end].chars().count().max(1);
+            let underline = marker.to_string().repeat(length);
+            let mut row = format!("{}{}", " ".repeat(column - 1), underline);
+            if !label.message.is_empty() {
+                row = format!("{} {}", row, label.message);
+            }
+            out += &format!("{} {}\n", gutter, self.paint(color, &row));
+        }
+        for note in &diagnostic.notes {
+            let indent = format!("\n{}         ", " ".repeat(width));
+            out += &format!(
+                "{} {} {}\n",
+                " ".repeat(width),
+                self.paint(BOLD, "= note:"),
+                note.replace('\n', &indent)
+            );
+        }
+        out
+    }
+
+    /// The 1-based line and column of `offset`, counting columns in characters.
+    fn position(lines: &LineIndex, source: &str, offset: usize) -> (usize, usize) {
+        let line = lines.line(offset);
+        let column = source[lines.line_start(line)..offset].chars().count() + 1;
+        (line, column)
+    }
+
+    /// The text of a 1-based line, without its line break.
+    fn text<'a>(lines: &LineIndex, source: &'a str, line: usize) -> &'a str {
+        let rest = &source[lines.line_start(line)..];
+        let text = rest.split('\n').next().unwrap_or("");
+        text.strip_suffix('\r').unwrap_or(text)
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.colored {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Diagnostic, Renderer};
+
+    #[test]
+    fn plain() {
+        let source = "{\n  var a = 1;\n  print a;\n  var a = a;\n}\n";
+        let diagnostic = Diagnostic::error("Already a variable with this name in this scope.")
+            .with_primary(32..33, "redeclared here")
+            .with_label(8..9, "previous declaration here")
+            .with_note("shadowing is only allowed in nested blocks\nlike this one");
+        let expected = "\
+error: Already a variable with this name in this scope.
+ --> test.lox:4:7
+  |
+2 |   var a = 1;
+  |       - previous declaration here
+...
+4 |   var a = a;
+  |       ^ redeclared here
+  = note: shadowing is only allowed in nested blocks
+          like this one
+";
+        assert_eq!(
+            Renderer::plain().render(&diagnostic, "test.lox", source),
+            expected
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let source = "print \"a\" + nil;\nprint";
+        let diagnostic = Diagnostic::warning("Operands")
+            .with_code("demo")
+            .with_primary(6..15, "");
+        let rendered = Renderer::plain().render(&diagnostic, "test.lox", source);
+        assert!(rendered.starts_with("warning[demo]: Operands\n --> test.lox:1:7\n"));
+        assert!(rendered.ends_with("1 | print \"a\" + nil;\n  |       ^^^^^^^^^\n"));
+
+        // A span at the end of the input still gets a caret.
+        let diagnostic = Diagnostic::error("Expect ';' after value.").with_primary(22..22, "");
+        let rendered = Renderer::plain().render(&diagnostic, "test.lox", source);
+        assert!(rendered.ends_with("2 | print\n  |      ^\n"));
+    }
+
+    #[test]
+    fn ansi() {
+        let diagnostic = Diagnostic::error("Unexpected character.").with_primary(0..1, "");
+        let rendered = Renderer::ansi().render(&diagnostic, "test.lox", "@");
+        assert!(
+            rendered.starts_with("\x1b[1;31merror\x1b[0m\x1b[1m: Unexpected character.\x1b[0m\n")
+        );
+        assert!(rendered.contains("\x1b[1;31m^\x1b[0m"));
+    }
+}
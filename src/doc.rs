@@ -0,0 +1,425 @@
+use crate::ast::{self, AstNode};
+use crate::error::SyntaxError;
+use crate::highlight::{escape, Highlighter, STYLESHEET};
+use crate::kinds::SyntaxKind;
+use crate::module::{FileLoader, ModuleLoader};
+use crate::parser::Parser;
+use crate::scanner::{self, Scanner};
+
+/// The kind of pages a [`DocGenerator`] writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocFormat {
+    Html,
+    Markdown,
+}
+
+impl DocFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            DocFormat::Html => "html",
+            DocFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Builds reference pages from the `///` comments in a directory of modules:
+/// an index, and a page per module listing its global variables and the
+/// modules it imports.
+///
+/// In doc text, `[name]` links to a variable in the same module, `[alias]`
+/// to an imported module and `[alias.name]` to an export of one.
+pub struct DocGenerator {
+    format: DocFormat,
+    modules: Vec<ModuleDoc>,
+}
+
+struct ModuleDoc {
+    /// Relative to the documented directory, with `/` separators.
+    path: String,
+    imports: Vec<ImportDoc>,
+    variables: Vec<VariableDoc>,
+}
+
+struct ImportDoc {
+    name: String,
+    /// The resolved path, as the module would be loaded.
+    path: String,
+}
+
+struct VariableDoc {
+    name: String,
+    /// The declaration without its `;`.
+    signature: String,
+    exported: bool,
+    doc: Option<String>,
+}
+
+impl DocGenerator {
+    pub fn new(format: DocFormat) -> DocGenerator {
+        DocGenerator {
+            format,
+            modules: Vec::new(),
+        }
+    }
+
+    /// Adds the module at `path`, relative to the documented directory.
+    pub fn add(&mut self, path: &str, source: &str) -> Result<(), Vec<SyntaxError>> {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan().cloned().collect();
+        if !scanner.errors().is_empty() {
+            return Err(scanner.errors().to_vec());
+        }
+        let statements = Parser::new(tokens).with_docs(scanner.comments()).parse()?;
+
+        let mut module = ModuleDoc {
+            path: path.to_string(),
+            imports: Vec::new(),
+            variables: Vec::new(),
+        };
+        for statement in &statements {
+            let (var_declaration, exported) = match statement.kind() {
+                SyntaxKind::Var => (ast::VarDeclaration::cast(statement.clone()).unwrap(), false),
+                SyntaxKind::Export => {
+                    let export = ast::Export::cast(statement.clone()).unwrap();
+                    (export.declaration(), true)
+                }
+                SyntaxKind::Import => {
+                    let import = ast::Import::cast(statement.clone()).unwrap();
+                    let imported = scanner::string_value(&import.path());
+                    module.imports.push(ImportDoc {
+                        name: import.name().text().to_string(),
+                        path: FileLoader.resolve(Some(path), &imported),
+                    });
+                    continue;
+                }
+                _ => continue,
+            };
+            let ident = var_declaration.ident();
+            let end = var_declaration
+                .syntax()
+                .span()
+                .map_or(ident.span().end, |span| span.end);
+            let keyword = if exported { "export var" } else { "var" };
+            module.variables.push(VariableDoc {
+                name: ident.text().to_string(),
+                signature: format!("{} {}", keyword, &source[ident.offset()..end]),
+                exported,
+                doc: var_declaration.doc(),
+            });
+        }
+        self.modules.push(module);
+        Ok(())
+    }
+
+    /// The index and a page per module, as paths relative to the output
+    /// directory and their contents.
+    pub fn pages(&self) -> Vec<(String, String)> {
+        let mut modules = self.modules.iter().collect::<Vec<_>>();
+        modules.sort_by(|a, b| a.path.cmp(&b.path));
+        let mut pages = vec![(self.index_page(), self.index(&modules))];
+        for module in modules {
+            pages.push((self.page(&module.path), self.module(module)));
+        }
+        pages
+    }
+
+    fn index_page(&self) -> String {
+        format!("index.{}", self.format.extension())
+    }
+
+    /// The page of the module at `path`. Keeping `.lox` means no module's
+    /// page can be the index.
+    fn page(&self, path: &str) -> String {
+        format!("{}.{}", path, self.format.extension())
+    }
+
+    fn index(&self, modules: &[&ModuleDoc]) -> String {
+        let mut out = self.heading(1, "Modules", None);
+        let items = modules.iter().map(|module| {
+            let page = self.page(&module.path);
+            let variables = module
+                .variables
+                .iter()
+                .map(|variable| self.link(&variable.name, &format!("{}#{}", page, variable.name)))
+                .collect::<Vec<_>>();
+            let item = self.link(&module.path, &page);
+            if variables.is_empty() {
+                item
+            } else {
+                format!("{}: {}", item, variables.join(", "))
+            }
+        });
+        out += &self.list(items);
+        self.finish("Modules", out)
+    }
+
+    fn module(&self, module: &ModuleDoc) -> String {
+        let mut out = self.heading(1, &module.path, None);
+        if !module.imports.is_empty() {
+            out += &self.heading(2, "Imports", None);
+            let items = module.imports.iter().map(|import| {
+                let name = match self.module_link(module, &import.name) {
+                    Some(href) => self.link(&import.name, &href),
+                    None => self.code(&import.name),
+                };
+                format!("{} from {}", name, self.code(&import.path))
+            });
+            out += &self.list(items);
+        }
+        if !module.variables.is_empty() {
+            out += &self.heading(2, "Variables", None);
+        }
+        for variable in &module.variables {
+            let mut title = self.code(&variable.name);
+            if variable.exported {
+                title += " (exported)";
+            }
+            out += &self.heading(3, &title, Some(&variable.name));
+            out += &self.source(&variable.signature);
+            if let Some(doc) = &variable.doc {
+                out += &self.doc_text(module, doc);
+            }
+        }
+        self.finish(&module.path, out)
+    }
+
+    /// Where `[target]` in a doc comment of `module` links to, relative to
+    /// the module's page.
+    fn target(&self, module: &ModuleDoc, target: &str) -> Option<String> {
+        if module
+            .variables
+            .iter()
+            .any(|variable| variable.name == target)
+        {
+            return Some(format!("#{}", target));
+        }
+        if let Some((alias, name)) = target.split_once('.') {
+            let imported = self.imported(module, alias)?;
+            let exports = imported
+                .variables
+                .iter()
+                .filter(|variable| variable.exported);
+            if exports.clone().any(|variable| variable.name == name) {
+                let page = relative(&self.page(&module.path), &self.page(&imported.path));
+                return Some(format!("{}#{}", page, name));
+            }
+            return None;
+        }
+        self.module_link(module, target)
+    }
+
+    /// The page of the module `module` imports as `alias`, if it is
+    /// documented.
+    fn module_link(&self, module: &ModuleDoc, alias: &str) -> Option<String> {
+        let imported = self.imported(module, alias)?;
+        Some(relative(
+            &self.page(&module.path),
+            &self.page(&imported.path),
+        ))
+    }
+
+    fn imported(&self, module: &ModuleDoc, alias: &str) -> Option<&ModuleDoc> {
+        let import = module.imports.iter().find(|import| import.name == alias)?;
+        self.modules
+            .iter()
+            .find(|imported| imported.path == import.path)
+    }
+
+    /// Renders doc text, with `` `code` `` spans and with each `[target]`
+    /// that names something linked to it.
+    fn doc_text(&self, module: &ModuleDoc, doc: &str) -> String {
+        let mut paragraphs = Vec::new();
+        for paragraph in doc.split("\n\n").filter(|text| !text.trim().is_empty()) {
+            let mut out = String::new();
+            for (index, piece) in paragraph.split('`').enumerate() {
+                if index % 2 == 1 {
+                    out += &self.code(piece);
+                    continue;
+                }
+                let mut rest = piece;
+                while let Some(open) = rest.find('[') {
+                    let close = match rest[open..].find(']') {
+                        Some(close) => open + close,
+                        None => break,
+                    };
+                    out += &self.text(&rest[..open]);
+                    let target = &rest[open + 1..close];
+                    let is_markdown_link = rest[close + 1..].starts_with('(');
+                    match self.target(module, target).filter(|_| !is_markdown_link) {
+                        Some(href) => out += &self.link(target, &href),
+                        None => out += &self.text(&rest[open..=close]),
+                    }
+                    rest = &rest[close + 1..];
+                }
+                out += &self.text(rest);
+            }
+            paragraphs.push(match self.format {
+                DocFormat::Html => format!("<p>{}</p>\n", out),
+                DocFormat::Markdown => format!("{}\n\n", out),
+            });
+        }
+        paragraphs.concat()
+    }
+
+    fn text(&self, text: &str) -> String {
+        match self.format {
+            DocFormat::Html => escape(text),
+            DocFormat::Markdown => text.to_string(),
+        }
+    }
+
+    fn code(&self, code: &str) -> String {
+        match self.format {
+            DocFormat::Html => format!("<code>{}</code>", escape(code)),
+            DocFormat::Markdown => format!("`{}`", code),
+        }
+    }
+
+    /// A link with its label as code.
+    fn link(&self, label: &str, href: &str) -> String {
+        match self.format {
+            DocFormat::Html => format!("<a href=\"{}\">{}</a>", escape(href), self.code(label)),
+            DocFormat::Markdown => format!("[{}]({})", self.code(label), href),
+        }
+    }
+
+    /// A heading whose text is already rendered, with an anchor for `id`.
+    fn heading(&self, level: usize, text: &str, id: Option<&str>) -> String {
+        match (self.format, id) {
+            (DocFormat::Html, Some(id)) => {
+                format!("<h{0} id=\"{1}\">{2}</h{0}>\n", level, escape(id), text)
+            }
+            (DocFormat::Html, None) => format!("<h{0}>{1}</h{0}>\n", level, escape(text)),
+            (DocFormat::Markdown, Some(id)) => {
+                format!(
+                    "<a id=\"{}\"></a>\n\n{} {}\n\n",
+                    id,
+                    "#".repeat(level),
+                    text
+                )
+            }
+            (DocFormat::Markdown, None) => format!("{} {}\n\n", "#".repeat(level), text),
+        }
+    }
+
+    fn list(&self, items: impl Iterator<Item = String>) -> String {
+        match self.format {
+            DocFormat::Html => {
+                let items = items.map(|item| format!("<li>{}</li>\n", item));
+                format!("<ul>\n{}</ul>\n", items.collect::<String>())
+            }
+            DocFormat::Markdown => {
+                let items = items.map(|item| format!("- {}\n", item));
+                format!("{}\n", items.collect::<String>())
+            }
+        }
+    }
+
+    fn source(&self, source: &str) -> String {
+        match self.format {
+            DocFormat::Html => format!(
+                "<pre class=\"lox\">{}</pre>\n",
+                Highlighter::html().highlight(source)
+            ),
+            DocFormat::Markdown => format!("```lox\n{}\n```\n\n", source),
+        }
+    }
+
+    fn finish(&self, title: &str, body: String) -> String {
+        match self.format {
+            DocFormat::Html => format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+                 <title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+                escape(title),
+                STYLESHEET,
+                body
+            ),
+            DocFormat::Markdown => body.trim_end().to_string() + "\n",
+        }
+    }
+}
+
+/// A link from the page at `from` to the page at `to`, both relative to the
+/// output directory.
+fn relative(from: &str, to: &str) -> String {
+    format!("{}{}", "../".repeat(from.matches('/').count()), to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DocFormat, DocGenerator};
+
+    fn pages(format: DocFormat, modules: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut generator = DocGenerator::new(format);
+        for (path, source) in modules {
+            generator.add(path, source).unwrap();
+        }
+        generator.pages()
+    }
+
+    #[test]
+    fn markdown() {
+        let pages = pages(
+            DocFormat::Markdown,
+            &[
+                (
+                    "lib/shapes.lox",
+                    "import \"../units.lox\" as units;\n\
+                     /// Sides of a square, in [units.metre]s.\n\
+                     /// Not [units.private], [side], [sides](x) or [none].\n\
+                     export var side = 4;\n",
+                ),
+                (
+                    "units.lox",
+                    "/// One.\nexport var metre = 1;\nvar private = 2;\n",
+                ),
+            ],
+        );
+        let paths = pages
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["index.md", "lib/shapes.lox.md", "units.lox.md"]);
+        assert_eq!(
+            pages[0].1,
+            "# Modules\n\n\
+             - [`lib/shapes.lox`](lib/shapes.lox.md): [`side`](lib/shapes.lox.md#side)\n\
+             - [`units.lox`](units.lox.md): [`metre`](units.lox.md#metre), \
+             [`private`](units.lox.md#private)\n"
+        );
+        assert_eq!(
+            pages[1].1,
+            "# lib/shapes.lox\n\n\
+             ## Imports\n\n\
+             - [`units`](../units.lox.md) from `units.lox`\n\n\
+             ## Variables\n\n\
+             <a id=\"side\"></a>\n\n\
+             ### `side` (exported)\n\n\
+             ```lox\nexport var side = 4\n```\n\n\
+             Sides of a square, in [`units.metre`](../units.lox.md#metre)s.\n\
+             Not [units.private], [`side`](#side), [sides](x) or [none].\n"
+        );
+    }
+
+    #[test]
+    fn html() {
+        let pages = pages(
+            DocFormat::Html,
+            &[("a.lox", "/// Uses `<b>` & [b].\nvar a = b;\nvar b;\n")],
+        );
+        let page = &pages[1].1;
+        assert!(page.contains("<title>a.lox</title>"));
+        assert!(page.contains("<h3 id=\"a\"><code>a</code></h3>"));
+        assert!(page.contains(
+            "<p>Uses <code>&lt;b&gt;</code> &amp; <a href=\"#b\"><code>b</code></a>.</p>"
+        ));
+        assert!(!page.contains("<p></p>"));
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let mut generator = DocGenerator::new(DocFormat::Html);
+        assert!(generator.add("a.lox", "var a").is_err());
+        assert!(generator.add("b.lox", "/* a").is_err());
+    }
+}
@@ -1,20 +1,14 @@
+use crate::intern::Symbol;
 use crate::value::Value;
 use std::collections::HashMap;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Environment {
     enclosing: Option<Box<Environment>>,
-    values: HashMap<String, Value>,
+    values: HashMap<Symbol, Value>,
 }
 
 impl Environment {
-    pub fn default() -> Environment {
-        Environment {
-            enclosing: None,
-            values: HashMap::new(),
-        }
-    }
-
     pub fn new(enclosing: Environment) -> Environment {
         Environment {
             enclosing: Some(Box::new(enclosing)),
@@ -26,9 +20,24 @@ impl Environment {
         self.enclosing.as_deref().cloned()
     }
 
-    pub fn assign(&mut self, name: &str, value: Value) {
-        if self.values.contains_key(name) {
-            self.values.insert(name.to_string(), value);
+    /// The outermost environment, where globals live.
+    pub fn global(&self) -> &Environment {
+        match self.enclosing.as_deref() {
+            Some(enclosing) => enclosing.global(),
+            None => self,
+        }
+    }
+
+    pub fn global_mut(&mut self) -> &mut Environment {
+        match self.enclosing {
+            Some(ref mut enclosing) => enclosing.global_mut(),
+            None => self,
+        }
+    }
+
+    pub fn assign(&mut self, name: &Symbol, value: Value) {
+        if let Some(slot) = self.values.get_mut(name) {
+            *slot = value;
             return;
         }
         if let Some(enclosing) = self.enclosing.as_mut() {
@@ -36,11 +45,11 @@ impl Environment {
         }
     }
 
-    pub fn define(&mut self, name: &str, value: Value) {
-        self.values.insert(name.to_string(), value);
+    pub fn define(&mut self, name: Symbol, value: Value) {
+        self.values.insert(name, value);
     }
 
-    pub fn get(&self, name: &str) -> Option<Value> {
+    pub fn get(&self, name: &Symbol) -> Option<Value> {
         if let Some(value) = self.values.get(name) {
             return Some(value.clone());
         }
@@ -0,0 +1,240 @@
+use crate::green::SyntaxToken;
+use crate::line_index::LineIndex;
+use crate::map::MapKey;
+use crate::value::Value;
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+/// What caused a [`RuntimeError`], so hosts can tell a failing script from
+/// one that was stopped for exceeding its [`Limits`](crate::Limits).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The script itself failed, e.g. with a type error or undefined variable.
+    Script,
+    StepLimit,
+    CallDepthLimit,
+    Timeout,
+    AllocationLimit,
+}
+
+/// One entry of a Lox backtrace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackFrame {
+    /// The called function, or `None` for the top level of the script.
+    pub function: Option<String>,
+    /// Where execution was in that function. Natives have no location.
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    kind: ErrorKind,
+    message: String,
+    trace: Vec<StackFrame>,
+    span: Option<Range<usize>>,
+    value: Option<Value>,
+}
+
+impl RuntimeError {
+    pub fn new(message: impl Into<String>) -> RuntimeError {
+        RuntimeError::with_kind(ErrorKind::Script, message)
+    }
+
+    pub fn with_kind(kind: ErrorKind, message: impl Into<String>) -> RuntimeError {
+        RuntimeError {
+            kind,
+            message: message.into(),
+            trace: Vec::new(),
+            span: None,
+            value: None,
+        }
+    }
+
+    /// An error carrying a value thrown by the script, which a `catch`
+    /// clause receives as it is.
+    ///
+    /// The message is the value's `message` entry if it is a map with a
+    /// string one, like a caught error being rethrown, and otherwise the
+    /// value itself.
+    pub fn thrown(value: Value) -> RuntimeError {
+        let message = match &value {
+            Value::Map(map) => match map.borrow().get(&MapKey::String("message".into())) {
+                Some(Value::String(message)) => message.to_string(),
+                _ => value.to_string(),
+            },
+            _ => value.to_string(),
+        };
+        RuntimeError {
+            value: Some(value),
+            ..RuntimeError::new(message)
+        }
+    }
+
+    pub(crate) fn with_trace(
+        self,
+        trace: Vec<StackFrame>,
+        span: Option<Range<usize>>,
+    ) -> RuntimeError {
+        RuntimeError {
+            trace,
+            span,
+            ..self
+        }
+    }
+
+    /// The error as seen by the `import` of the module at `path` it was
+    /// raised in, at `line` of that module.
+    pub(crate) fn in_module(self, path: &str, line: Option<usize>) -> RuntimeError {
+        let location = match line {
+            Some(line) => format!("line {} of '{}'", line, path),
+            None => format!("'{}'", path),
+        };
+        RuntimeError {
+            message: format!("{} [{}]", self.message, location),
+            trace: Vec::new(),
+            span: None,
+            ..self
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The value the script threw, or `None` for an error raised by the
+    /// interpreter or a native.
+    pub fn value(&self) -> Option<&Value> {
+        self.value.as_ref()
+    }
+
+    /// The calls that were active when the error occurred, innermost first.
+    /// Empty until the error has passed through the interpreter.
+    pub fn trace(&self) -> &[StackFrame] {
+        &self.trace
+    }
+
+    /// The source range of the innermost expression or call that failed.
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.span.clone()
+    }
+
+    /// Formats the trace one frame per line, like `[line 12] in inner()`.
+    pub fn backtrace(&self, lines: &LineIndex) -> String {
+        let frames = self.trace.iter().map(|frame| {
+            let location = match frame.offset {
+                Some(offset) => format!("[line {}]", lines.line(offset)),
+                None => "[native]".to_string(),
+            };
+            match &frame.function {
+                Some(function) => format!("{} in {}()", location, function),
+                None => format!("{} in script", location),
+            }
+        });
+        frames.collect::<Vec<_>>().join("\n")
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.message, f)
+    }
+}
+
+impl Error for RuntimeError {}
+
+/// An error found while scanning or parsing, located by byte offset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    message: String,
+    span: Range<usize>,
+    location: String,
+    labels: Vec<(Range<usize>, String)>,
+}
+
+impl SyntaxError {
+    /// An error that is not tied to a complete token, like an unexpected character.
+    pub fn new(message: impl Into<String>, span: Range<usize>) -> SyntaxError {
+        SyntaxError {
+            message: message.into(),
+            span,
+            location: String::new(),
+            labels: Vec::new(),
+        }
+    }
+
+    pub fn at_token(message: impl Into<String>, token: &SyntaxToken) -> SyntaxError {
+        SyntaxError {
+            location: format!(" at '{}'", token.text()),
+            ..SyntaxError::new(message, token.span())
+        }
+    }
+
+    pub fn at_end(message: impl Into<String>, offset: usize) -> SyntaxError {
+        SyntaxError {
+            location: " at end".to_string(),
+            ..SyntaxError::new(message, offset..offset)
+        }
+    }
+
+    /// Points at related source, like the declaration a use conflicts with.
+    pub fn with_label(mut self, span: Range<usize>, message: impl Into<String>) -> SyntaxError {
+        self.labels.push((span, message.into()));
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn offset(&self) -> usize {
+        self.span.start
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    pub fn labels(&self) -> &[(Range<usize>, String)] {
+        &self.labels
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Error{}: {}", self.location, self.message)
+    }
+}
+
+impl Error for SyntaxError {}
+
+/// Any error from running Lox source through the [`Lox`](crate::Lox) facade.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoxError {
+    Syntax(Vec<SyntaxError>),
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for LoxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoxError::Syntax(errors) => {
+                let messages = errors.iter().map(ToString::to_string);
+                fmt::Display::fmt(&messages.collect::<Vec<_>>().join("\n"), f)
+            }
+            LoxError::Runtime(error) => fmt::Display::fmt(error, f),
+        }
+    }
+}
+
+impl Error for LoxError {}
+
+impl From<RuntimeError> for LoxError {
+    fn from(error: RuntimeError) -> LoxError {
+        LoxError::Runtime(error)
+    }
+}
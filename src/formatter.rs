@@ -0,0 +1,661 @@
+use crate::ast::{self, AstNode};
+use crate::error::SyntaxError;
+use crate::green::{NodeOrToken, SyntaxNode, SyntaxToken};
+use crate::kinds::SyntaxKind;
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+use std::ops::Range;
+
+/// Prints Lox source in one canonical layout.
+///
+/// Blocks are indented, binary operators are surrounded by spaces, opening
+/// braces stay on the line of their `if` or `while`, and expressions that do
+/// not fit in `max_width` columns are split over several lines. Comments and
+/// single blank lines between statements are kept.
+pub struct Formatter {
+    pub max_width: usize,
+    /// Spaces per level of nesting.
+    pub indent: usize,
+}
+
+impl Default for Formatter {
+    fn default() -> Formatter {
+        Formatter {
+            max_width: 80,
+            indent: 4,
+        }
+    }
+}
+
+impl Formatter {
+    pub fn new() -> Formatter {
+        Formatter::default()
+    }
+
+    /// Formats `source`, which must be free of syntax errors.
+    pub fn format(&self, source: &str) -> Result<String, Vec<SyntaxError>> {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan().cloned().collect::<Vec<_>>();
+        if !scanner.errors().is_empty() {
+            return Err(scanner.errors().to_vec());
+        }
+        let statements = Parser::new(tokens.clone()).parse()?;
+
+        let mut printer = Printer {
+            formatter: self,
+            source,
+            tokens: &tokens,
+            comments: scanner.comments(),
+            out: String::new(),
+            last_end: None,
+        };
+        for statement in &statements {
+            printer.list_item(statement, 0);
+        }
+        printer.comments_before(usize::MAX, 0);
+        Ok(printer.out)
+    }
+}
+
+struct Printer<'a> {
+    formatter: &'a Formatter,
+    source: &'a str,
+    /// Every token, including the keywords and `;`s the tree leaves out.
+    tokens: &'a [SyntaxToken],
+    /// Comments not printed yet, in source order.
+    comments: &'a [SyntaxToken],
+    out: String,
+    /// Where the last printed statement or comment ended in the source.
+    last_end: Option<usize>,
+}
+
+impl Printer<'_> {
+    /// Prints one of a sequence of statements on its own lines.
+    fn list_item(&mut self, syntax_node: &SyntaxNode, depth: usize) {
+        let start = self.inline_comments_start(self.extent(syntax_node).start);
+        self.comments_before(start, depth);
+        self.blank_line_before(start);
+        self.statement(syntax_node, depth);
+        self.out.push('\n');
+    }
+
+    /// Prints a statement from the start of a line, without its line break.
+    /// A statement with a comment inside it is printed as it was written,
+    /// so the comment stays where it is.
+    fn statement(&mut self, syntax_node: &SyntaxNode, depth: usize) {
+        let extent = self.extent(syntax_node);
+        self.comments_before(self.inline_comments_start(extent.start), depth);
+        self.out.push_str(&self.indent(depth));
+        while let Some((comment, rest)) = self.comments.split_first() {
+            if comment.offset() >= extent.start {
+                break;
+            }
+            self.out.push_str(&format!("{} ", comment.text()));
+            self.comments = rest;
+        }
+        if self.has_inner_comments(syntax_node, &extent) {
+            self.out.push_str(&self.source[extent.clone()]);
+            while self
+                .comments
+                .first()
+                .is_some_and(|c| c.offset() < extent.end)
+            {
+                self.comments = &self.comments[1..];
+            }
+        } else {
+            self.statement_inline(syntax_node, depth);
+        }
+        self.last_end = Some(extent.end);
+        self.trailing_comment(extent.end);
+    }
+
+    /// The source a statement was parsed from, including the keywords that
+    /// start it and the `;` that ends it, which the tree leaves out.
+    fn extent(&self, syntax_node: &SyntaxNode) -> Range<usize> {
+        let span = syntax_node.span().unwrap_or(0..0);
+        let first = self
+            .tokens
+            .partition_point(|token| token.offset() < span.start);
+        let skipped = match syntax_node.kind() {
+            SyntaxKind::Var => 1,
+            SyntaxKind::If | SyntaxKind::While => 2,
+            _ => 0,
+        };
+        let start = self.tokens[first.saturating_sub(skipped)..]
+            .first()
+            .map_or(span.start, SyntaxToken::offset);
+        let next = self
+            .tokens
+            .partition_point(|token| token.offset() < span.end);
+        let end = match self.tokens.get(next) {
+            Some(token) if token.kind() == SyntaxKind::Semicolon => token.span().end,
+            _ => span.end,
+        };
+        start..end
+    }
+
+    /// Whether a comment not printed yet is inside the statement at
+    /// `extent`, other than in the statements nested in it.
+    fn has_inner_comments(&self, syntax_node: &SyntaxNode, extent: &Range<usize>) -> bool {
+        let bodies = match syntax_node.kind() {
+            // The block prints the comments between its statements itself.
+            SyntaxKind::Block => return false,
+            SyntaxKind::If => {
+                let if_condition = ast::If::cast(syntax_node.clone()).unwrap();
+                let mut bodies = vec![if_condition.then_branch()];
+                bodies.extend(if_condition.else_branch());
+                bodies
+            }
+            SyntaxKind::While => vec![ast::While::cast(syntax_node.clone()).unwrap().body()],
+            SyntaxKind::For => vec![ast::For::cast(syntax_node.clone()).unwrap().body()],
+            SyntaxKind::Try => {
+                let try_statement = ast::Try::cast(syntax_node.clone()).unwrap();
+                let mut bodies = vec![try_statement.body()];
+                bodies.extend(try_statement.catch_clause().map(|catch| catch.body()));
+                bodies.extend(try_statement.finally_block());
+                bodies
+            }
+            _ => Vec::new(),
+        };
+        let bodies = bodies
+            .iter()
+            .map(|body| self.extent(body))
+            .collect::<Vec<_>>();
+        self.comments
+            .iter()
+            .take_while(|comment| comment.offset() < extent.end)
+            .any(|comment| {
+                comment.offset() >= extent.start
+                    && !bodies.iter().any(|body| body.contains(&comment.offset()))
+            })
+    }
+
+    fn statement_inline(&mut self, syntax_node: &SyntaxNode, depth: usize) {
+        let column = self.column();
+        match syntax_node.kind() {
+            SyntaxKind::Print => {
+                let print = ast::Print::cast(syntax_node.clone()).unwrap();
+                let expr = self.expr(&print.expr(), column + "print ".len(), depth);
+                self.out.push_str(&format!("print {};", expr));
+            }
+            SyntaxKind::Var => {
+                let var_declaration = ast::VarDeclaration::cast(syntax_node.clone()).unwrap();
+                let name = var_declaration.ident();
+                let initializer = var_declaration.initializer();
+                if initializer.kind() == SyntaxKind::Nil {
+                    self.out.push_str(&format!("var {};", name));
+                } else {
+                    let prefix = format!("var {} = ", name);
+                    let expr = self.expr(&initializer, column + prefix.len(), depth);
+                    self.out.push_str(&format!("{}{};", prefix, expr));
+                }
+            }
+            SyntaxKind::Block => self.block(syntax_node, depth),
+            SyntaxKind::If => {
+                let if_condition = ast::If::cast(syntax_node.clone()).unwrap();
+                self.condition("if", &if_condition.condition(), depth);
+                let then_branch = if_condition.then_branch();
+                self.body(&then_branch, depth);
+                if let Some(else_branch) = if_condition.else_branch() {
+                    if then_branch.kind() == SyntaxKind::Block {
+                        self.out.push_str(" else");
+                    } else {
+                        self.out.push('\n');
+                        self.out.push_str(&format!("{}else", self.indent(depth)));
+                    }
+                    if else_branch.kind() == SyntaxKind::If {
+                        self.out.push(' ');
+                        self.statement_inline(&else_branch, depth);
+                    } else {
+                        self.body(&else_branch, depth);
+                    }
+                }
+            }
+            SyntaxKind::While => {
+                let while_condition = ast::While::cast(syntax_node.clone()).unwrap();
+                self.condition("while", &while_condition.condition(), depth);
+                self.body(&while_condition.body(), depth);
+            }
+            SyntaxKind::For => {
+                let for_loop = ast::For::cast(syntax_node.clone()).unwrap();
+                self.out.push_str("for (");
+                match for_loop.initializer() {
+                    Some(initializer) => self.statement_inline(&initializer, depth),
+                    None => self.out.push(';'),
+                }
+                if let Some(condition) = for_loop.condition() {
+                    let expr = self.expr(&condition, self.column() + 1, depth);
+                    self.out.push_str(&format!(" {}", expr));
+                }
+                self.out.push(';');
+                if let Some(increment) = for_loop.increment() {
+                    let expr = self.expr(&increment, self.column() + 1, depth);
+                    self.out.push_str(&format!(" {}", expr));
+                }
+                self.out.push(')');
+                self.body(&for_loop.body(), depth);
+            }
+            SyntaxKind::Import => {
+                let import = ast::Import::cast(syntax_node.clone()).unwrap();
+                self.out
+                    .push_str(&format!("import {} as {};", import.path(), import.name()));
+            }
+            SyntaxKind::Export => {
+                let export = ast::Export::cast(syntax_node.clone()).unwrap();
+                self.out.push_str("export ");
+                self.statement_inline(export.declaration().syntax(), depth);
+            }
+            SyntaxKind::Throw => {
+                let throw = ast::Throw::cast(syntax_node.clone()).unwrap();
+                let expr = self.expr(&throw.expr(), column + "throw ".len(), depth);
+                self.out.push_str(&format!("throw {};", expr));
+            }
+            SyntaxKind::Try => {
+                let try_statement = ast::Try::cast(syntax_node.clone()).unwrap();
+                self.out.push_str("try ");
+                self.block(&try_statement.body(), depth);
+                if let Some(catch) = try_statement.catch_clause() {
+                    self.out.push_str(&format!(" catch ({}) ", catch.name()));
+                    self.block(&catch.body(), depth);
+                }
+                if let Some(finally) = try_statement.finally_block() {
+                    self.out.push_str(" finally ");
+                    self.block(&finally, depth);
+                }
+            }
+            SyntaxKind::Break => self.out.push_str("break;"),
+            SyntaxKind::Continue => self.out.push_str("continue;"),
+            _ => {
+                let expr = self.expr(syntax_node, column, depth);
+                self.out.push_str(&format!("{};", expr));
+            }
+        }
+    }
+
+    fn condition(&mut self, keyword: &str, condition: &SyntaxNode, depth: usize) {
+        let column = self.column() + keyword.len() + " (".len();
+        let condition = self.expr(condition, column, depth);
+        self.out.push_str(&format!("{} ({})", keyword, condition));
+    }
+
+    /// The body of an `if` or a loop: a block on the same line, anything
+    /// else indented on the next.
+    fn body(&mut self, syntax_node: &SyntaxNode, depth: usize) {
+        if syntax_node.kind() == SyntaxKind::Block {
+            self.out.push(' ');
+            self.block(syntax_node, depth);
+        } else {
+            self.out.push('\n');
+            self.statement(syntax_node, depth + 1);
+        }
+    }
+
+    fn block(&mut self, syntax_node: &SyntaxNode, depth: usize) {
+        let block = ast::Block::cast(syntax_node.clone()).unwrap();
+        let end = syntax_node.span().map_or(0, |span| span.end);
+        let statements = block.children().collect::<Vec<_>>();
+        let has_comments = self.comments.first().is_some_and(|c| c.offset() < end);
+        if statements.is_empty() && !has_comments {
+            self.out.push_str("{}");
+            return;
+        }
+        self.out.push_str("{\n");
+        self.last_end = None;
+        for statement in &statements {
+            self.list_item(statement, depth + 1);
+        }
+        self.comments_before(end, depth + 1);
+        self.out.push_str(&format!("{}}}", self.indent(depth)));
+    }
+
+    /// Prints the comments that start before `offset`, each on its own line.
+    fn comments_before(&mut self, offset: usize, depth: usize) {
+        while let Some((comment, rest)) = self.comments.split_first() {
+            if comment.offset() >= offset {
+                break;
+            }
+            self.comments = rest;
+            self.blank_line_before(comment.offset());
+            self.out
+                .push_str(&format!("{}{}\n", self.indent(depth), comment.text()));
+            self.last_end = Some(comment.span().end);
+        }
+    }
+
+    /// Where the `/* */` comments on the same line as a statement starting
+    /// at `start`, and in front of it, begin. They stay in front of it.
+    fn inline_comments_start(&self, start: usize) -> usize {
+        let before = self
+            .comments
+            .iter()
+            .take_while(|comment| comment.offset() < start);
+        let mut inline_start = start;
+        for comment in before.collect::<Vec<_>>().into_iter().rev() {
+            let between = &self.source[comment.span().end..inline_start];
+            if !comment.text().starts_with("/*") || between.contains('\n') {
+                break;
+            }
+            inline_start = comment.offset();
+        }
+        inline_start
+    }
+
+    /// Appends the comments that follow `end` on the same source line, with
+    /// nothing but spaces before them. A `/* */` comment with code after it
+    /// on its line is left to go in front of that code.
+    fn trailing_comment(&mut self, mut end: usize) {
+        while let Some((comment, rest)) = self.comments.split_first() {
+            let between = self.source.get(end..comment.offset()).unwrap_or("\n");
+            if !between.trim().is_empty() || between.contains('\n') {
+                return;
+            }
+            if comment.text().starts_with("/*") {
+                let next = self
+                    .tokens
+                    .partition_point(|token| token.offset() < comment.span().end);
+                let next = self.tokens.get(next);
+                let after =
+                    next.map_or("\n", |next| &self.source[comment.span().end..next.offset()]);
+                if !after.contains('\n') {
+                    return;
+                }
+            }
+            self.out.push_str(&format!(" {}", comment.text()));
+            end = comment.span().end;
+            self.last_end = Some(end);
+            self.comments = rest;
+        }
+    }
+
+    /// Keeps one blank line where the source had at least one.
+    fn blank_line_before(&mut self, offset: usize) {
+        if let Some(end) = self.last_end {
+            let between = self.source.get(end..offset).unwrap_or("");
+            if between.matches('\n').count() > 1 {
+                self.out.push('\n');
+            }
+        }
+    }
+
+    /// Formats an expression starting at `column`, splitting it over several
+    /// lines if it does not fit.
+    fn expr(&self, syntax_node: &SyntaxNode, column: usize, depth: usize) -> String {
+        let flat = flat(syntax_node);
+        // Leave room for the `;` or `)` that usually follows.
+        if column + flat.len() < self.formatter.max_width {
+            return flat;
+        }
+        let continuation = self.indent(depth + 1);
+        match syntax_node.kind() {
+            SyntaxKind::BinExpr | SyntaxKind::And | SyntaxKind::Or => {
+                let (first, rest) = chain(syntax_node);
+                let mut text = self.expr(&first, column, depth);
+                for (op, operand) in rest {
+                    let column = continuation.len() + op.text().len() + 1;
+                    let operand = self.expr(&operand, column, depth + 1);
+                    text.push_str(&format!("\n{}{} {}", continuation, op, operand));
+                }
+                text
+            }
+            SyntaxKind::Call => {
+                let call = ast::Call::cast(syntax_node.clone()).unwrap();
+                let arguments = call
+                    .arguments()
+                    .map(|argument| {
+                        let argument = self.expr(&argument, continuation.len(), depth + 1);
+                        format!("{}{}", continuation, argument)
+                    })
+                    .collect::<Vec<_>>();
+                if arguments.is_empty() {
+                    return flat;
+                }
+                format!(
+                    "{}(\n{}\n{})",
+                    self.expr(&call.callee(), column, depth),
+                    arguments.join(",\n"),
+                    self.indent(depth)
+                )
+            }
+            SyntaxKind::Assign => {
+                let assign = ast::Assign::cast(syntax_node.clone()).unwrap();
+                let prefix = format!("{} = ", self::flat(&assign.target()));
+                let value = self.expr(&assign.value(), column + prefix.len(), depth);
+                format!("{}{}", prefix, value)
+            }
+            _ => flat,
+        }
+    }
+
+    fn indent(&self, depth: usize) -> String {
+        " ".repeat(depth * self.formatter.indent)
+    }
+
+    /// The column the next character printed will be in.
+    fn column(&self) -> usize {
+        let line_start = self.out.rfind('\n').map_or(0, |newline| newline + 1);
+        self.out[line_start..].chars().count()
+    }
+}
+
+/// An expression on a single line.
+fn flat(syntax_node: &SyntaxNode) -> String {
+    match syntax_node.kind() {
+        SyntaxKind::UnaryExpr => {
+            let unary_expr = ast::UnaryExpr::cast(syntax_node.clone()).unwrap();
+            format!("{}{}", unary_expr.op(), flat(&unary_expr.node()))
+        }
+        SyntaxKind::BinExpr | SyntaxKind::And | SyntaxKind::Or => {
+            let (first, rest) = chain(syntax_node);
+            let mut text = flat(&first);
+            for (op, operand) in rest {
+                text.push_str(&format!(" {} {}", op, flat(&operand)));
+            }
+            text
+        }
+        SyntaxKind::Assign => {
+            let assign = ast::Assign::cast(syntax_node.clone()).unwrap();
+            format!("{} = {}", flat(&assign.target()), flat(&assign.value()))
+        }
+        SyntaxKind::ListLiteral => {
+            let list = ast::ListLiteral::cast(syntax_node.clone()).unwrap();
+            let elements = list.elements().map(|element| flat(&element));
+            format!("[{}]", elements.collect::<Vec<_>>().join(", "))
+        }
+        SyntaxKind::MapLiteral => {
+            let map = ast::MapLiteral::cast(syntax_node.clone()).unwrap();
+            let entries = map
+                .entries()
+                .iter()
+                .map(|(key, value)| format!("{}: {}", flat(key), flat(value)))
+                .collect::<Vec<_>>();
+            format!("{{{}}}", entries.join(", "))
+        }
+        SyntaxKind::Interpolation => syntax_node
+            .children()
+            .map(|part| match part {
+                NodeOrToken::Token(token) => token.text().to_string(),
+                NodeOrToken::Node(expr) => flat(&expr),
+            })
+            .collect(),
+        SyntaxKind::Get => {
+            let get = ast::Get::cast(syntax_node.clone()).unwrap();
+            format!("{}.{}", flat(&get.object()), get.name())
+        }
+        SyntaxKind::Index => {
+            let index = ast::Index::cast(syntax_node.clone()).unwrap();
+            format!("{}[{}]", flat(&index.target()), flat(&index.index()))
+        }
+        SyntaxKind::Call => {
+            let call = ast::Call::cast(syntax_node.clone()).unwrap();
+            let arguments = call.arguments().map(|argument| flat(&argument));
+            format!(
+                "{}({})",
+                flat(&call.callee()),
+                arguments.collect::<Vec<_>>().join(", ")
+            )
+        }
+        _ => syntax_node.to_string(),
+    }
+}
+
+/// Splits a left-associative run of operators with the same precedence, like
+/// `a + b - c`, into its first operand and the `(operator, operand)` pairs
+/// that follow.
+fn chain(syntax_node: &SyntaxNode) -> (SyntaxNode, Vec<(SyntaxToken, SyntaxNode)>) {
+    let (left, op, right) = operands(syntax_node);
+    let mut rest = vec![(op.clone(), right)];
+    let mut first = left;
+    while let Some((left, inner_op, right)) = Some(&first)
+        .filter(|node| node.kind() == syntax_node.kind())
+        .map(operands)
+    {
+        if precedence(&inner_op) != precedence(&op) {
+            break;
+        }
+        rest.insert(0, (inner_op, right));
+        first = left;
+    }
+    (first, rest)
+}
+
+fn operands(syntax_node: &SyntaxNode) -> (SyntaxNode, SyntaxToken, SyntaxNode) {
+    match ast::BinExpr::cast(syntax_node.clone()) {
+        Some(bin_expr) => (bin_expr.left(), bin_expr.op(), bin_expr.right()),
+        None => {
+            let logical = ast::Logical::cast(syntax_node.clone()).unwrap();
+            (logical.left(), logical.op(), logical.right())
+        }
+    }
+}
+
+fn precedence(op: &SyntaxToken) -> u8 {
+    match op.kind() {
+        SyntaxKind::Or => 0,
+        SyntaxKind::And => 1,
+        SyntaxKind::EqualEqual | SyntaxKind::BangEqual => 2,
+        SyntaxKind::Plus | SyntaxKind::Minus => 4,
+        SyntaxKind::Star | SyntaxKind::Slash => 5,
+        _ => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Formatter;
+
+    fn check_format(source: &str, expected: &str) {
+        let formatter = Formatter {
+            max_width: 40,
+            ..Formatter::default()
+        };
+        let formatted = formatter.format(source).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(formatter.format(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn statements() {
+        check_format(
+            "var a=1;var b;print a+-b*2==3 and !c;a=b=clock( );",
+            "var a = 1;\nvar b;\nprint a + -b * 2 == 3 and !c;\na = b = clock();\n",
+        );
+        check_format(
+            "{}{{print 1;}}",
+            "{}\n{\n    {\n        print 1;\n    }\n}\n",
+        );
+        check_format(
+            "var xs=[1,a+b,[ ]];xs[0]=xs [len(xs)-1];",
+            "var xs = [1, a + b, []];\nxs[0] = xs[len(xs) - 1];\n",
+        );
+        check_format("print {\"a\":1,b:{ }};", "print {\"a\": 1, b: {}};\n");
+        check_format(
+            "print \"a\\n${ b+1 } \\u{41}${\"c${d}\"}\";",
+            "print \"a\\n${b + 1} \\u{41}${\"c${d}\"}\";\n",
+        );
+        check_format(
+            "import \"lib/m.lox\"as m;export var a=m . b[0].c;",
+            "import \"lib/m.lox\" as m;\nexport var a = m.b[0].c;\n",
+        );
+    }
+
+    #[test]
+    fn braces() {
+        check_format(
+            "if(a){print 1;}else if(b)print 2;else{print 3;}",
+            "if (a) {\n    print 1;\n} else if (b)\n    print 2;\nelse {\n    print 3;\n}\n",
+        );
+        check_format(
+            "while(a<10)\n{a=a+1;}",
+            "while (a < 10) {\n    a = a + 1;\n}\n",
+        );
+        check_format(
+            "for(var i=0;i<3;i=i+1){if(i==1)continue;break;}for(;;)break;",
+            "for (var i = 0; i < 3; i = i + 1) {\n    if (i == 1)\n        continue;\n    break;\n}\nfor (;;)\n    break;\n",
+        );
+        check_format(
+            "try{throw \"x\";}catch(e){print e;}finally{}",
+            "try {\n    throw \"x\";\n} catch (e) {\n    print e;\n} finally {}\n",
+        );
+    }
+
+    #[test]
+    fn comments_and_blank_lines() {
+        check_format(
+            "// header\nvar a = 1;   // one\n\n\n\nprint a;\n{\n  // inside\n  print a; // trailing\n  // last\n}\n// end\n",
+            "// header\nvar a = 1; // one\n\nprint a;\n{\n    // inside\n    print a; // trailing\n    // last\n}\n// end\n",
+        );
+        check_format(
+            "/// doc\nvar a;   /* b */\n{ /* c\n   d */ }\n",
+            "/// doc\nvar a; /* b */\n{\n    /* c\n   d */\n}\n",
+        );
+    }
+
+    #[test]
+    fn comments_inside_statements() {
+        check_format(
+            "var a = 1; var b = 2; // b\n",
+            "var a = 1;\nvar b = 2; // b\n",
+        );
+        check_format(
+            "print 1 + // c\n2;\nwhile (x) print 1 + // d\n  2;\n",
+            "print 1 + // c\n2;\nwhile (x)\n    print 1 + // d\n  2;\n",
+        );
+        check_format(
+            "if (a // c\n) { print a; } else print b;",
+            "if (a // c\n) { print a; } else print b;\n",
+        );
+    }
+
+    #[test]
+    fn block_comments() {
+        check_format("var a = /* x */ 1;", "var a = /* x */ 1;\n");
+        check_format("print 1; /* a */ print 2;", "print 1;\n/* a */ print 2;\n");
+        check_format(
+            "print 1; /* a */ // b\n{ /* c */ /* d */ print 3; /* e */ }",
+            "print 1; /* a */ // b\n{\n    /* c */ /* d */ print 3;\n    /* e */\n}\n",
+        );
+        check_format("/* a */\nprint 1 /* b */;\n", "/* a */\nprint 1 /* b */;\n");
+    }
+
+    #[test]
+    fn long_lines() {
+        check_format(
+            "print first + second - third * fourth + fifth;",
+            "print first\n    + second\n    - third * fourth\n    + fifth;\n",
+        );
+        check_format(
+            "{ describe(\"a long argument\", another, 3); }",
+            "{\n    describe(\n        \"a long argument\",\n        another,\n        3\n    );\n}\n",
+        );
+        check_format(
+            "if (ready and steady and clock() > 10000) print 1;",
+            "if (ready\n    and steady\n    and clock() > 10000)\n    print 1;\n",
+        );
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert!(Formatter::new().format("print 1").is_err());
+        assert!(Formatter::new().format("print @;").is_err());
+    }
+}
@@ -1,5 +1,7 @@
+use crate::intern::Symbol;
 use crate::kinds::SyntaxKind;
 use std::fmt;
+use std::ops::Range;
 
 #[derive(Debug, Clone)]
 pub enum NodeOrToken<N, T> {
@@ -28,12 +30,17 @@ pub type SyntaxElement = NodeOrToken<SyntaxNode, SyntaxToken>;
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct SyntaxToken {
     kind: SyntaxKind,
-    text: String,
+    text: Symbol,
+    offset: usize,
 }
 
 impl SyntaxToken {
-    pub fn new(kind: SyntaxKind, text: String) -> SyntaxToken {
-        SyntaxToken { kind, text }
+    pub fn new(kind: SyntaxKind, text: &str, offset: usize) -> SyntaxToken {
+        SyntaxToken {
+            kind,
+            text: Symbol::intern(text),
+            offset,
+        }
     }
 
     pub fn kind(&self) -> SyntaxKind {
@@ -43,6 +50,20 @@ impl SyntaxToken {
     pub fn text(&self) -> &str {
         self.text.as_str()
     }
+
+    pub fn symbol(&self) -> Symbol {
+        self.text.clone()
+    }
+
+    /// Byte offset of the token's first character in the source.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The source range the token's text occupies.
+    pub fn span(&self) -> Range<usize> {
+        self.offset..self.offset + self.text().len()
+    }
 }
 
 impl fmt::Display for SyntaxToken {
@@ -79,6 +100,29 @@ impl SyntaxNode {
     pub fn children(&self) -> impl Iterator<Item = SyntaxElement> + '_ {
         self.children.iter().cloned()
     }
+
+    /// The leftmost token in this subtree, if it has any.
+    pub fn first_token(&self) -> Option<SyntaxToken> {
+        self.children.iter().find_map(|child| match child {
+            NodeOrToken::Node(node) => node.first_token(),
+            NodeOrToken::Token(token) => Some(token.clone()),
+        })
+    }
+
+    /// The rightmost token in this subtree, if it has any.
+    pub fn last_token(&self) -> Option<SyntaxToken> {
+        self.children.iter().rev().find_map(|child| match child {
+            NodeOrToken::Node(node) => node.last_token(),
+            NodeOrToken::Token(token) => Some(token.clone()),
+        })
+    }
+
+    /// The source range from the first to the last token of this subtree.
+    pub fn span(&self) -> Option<Range<usize>> {
+        let start = self.first_token()?.offset();
+        let end = self.last_token()?.span().end;
+        Some(start..end)
+    }
 }
 
 impl From<SyntaxNode> for SyntaxElement {
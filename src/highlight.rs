@@ -0,0 +1,199 @@
+use crate::green::SyntaxToken;
+use crate::kinds::SyntaxKind;
+use crate::scanner::Scanner;
+
+const RESET: &str = "\x1b[0m";
+
+/// The class of a token for highlighting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    Keyword,
+    Identifier,
+    String,
+    Number,
+    Operator,
+    Punctuation,
+    Comment,
+}
+
+impl Highlight {
+    pub fn of(kind: SyntaxKind) -> Highlight {
+        match kind {
+            SyntaxKind::Identifier => Highlight::Identifier,
+            SyntaxKind::String
+            | SyntaxKind::StringHead
+            | SyntaxKind::StringMiddle
+            | SyntaxKind::StringTail => Highlight::String,
+            SyntaxKind::Number => Highlight::Number,
+            SyntaxKind::Comment | SyntaxKind::DocComment => Highlight::Comment,
+            SyntaxKind::Minus
+            | SyntaxKind::Plus
+            | SyntaxKind::Slash
+            | SyntaxKind::Star
+            | SyntaxKind::Bang
+            | SyntaxKind::BangEqual
+            | SyntaxKind::Equal
+            | SyntaxKind::EqualEqual
+            | SyntaxKind::Greater
+            | SyntaxKind::GreaterEqual
+            | SyntaxKind::Less
+            | SyntaxKind::LessEqual => Highlight::Operator,
+            SyntaxKind::LeftParen
+            | SyntaxKind::RightParen
+            | SyntaxKind::LeftBrace
+            | SyntaxKind::RightBrace
+            | SyntaxKind::LeftBracket
+            | SyntaxKind::RightBracket
+            | SyntaxKind::Comma
+            | SyntaxKind::Colon
+            | SyntaxKind::Dot
+            | SyntaxKind::Semicolon => Highlight::Punctuation,
+            _ => Highlight::Keyword,
+        }
+    }
+
+    /// The CSS class of the token in HTML output.
+    pub fn class(self) -> &'static str {
+        match self {
+            Highlight::Keyword => "keyword",
+            Highlight::Identifier => "identifier",
+            Highlight::String => "string",
+            Highlight::Number => "number",
+            Highlight::Operator => "operator",
+            Highlight::Punctuation => "punctuation",
+            Highlight::Comment => "comment",
+        }
+    }
+
+    fn color(self) -> Option<&'static str> {
+        match self {
+            Highlight::Keyword => Some("\x1b[35m"),
+            Highlight::String => Some("\x1b[32m"),
+            Highlight::Number => Some("\x1b[36m"),
+            Highlight::Comment => Some("\x1b[90m"),
+            Highlight::Identifier | Highlight::Operator | Highlight::Punctuation => None,
+        }
+    }
+}
+
+/// Styles for the classes of [`Highlighter::html`] output inside a
+/// `<pre class="lox">`.
+pub const STYLESHEET: &str = "\
+pre.lox { background: #fafafa; padding: 1em; }
+.lox .keyword { color: #a626a4; font-weight: bold; }
+.lox .string { color: #50a14f; }
+.lox .number { color: #0184bc; }
+.lox .operator { color: #383a42; }
+.lox .comment { color: #a0a1a7; font-style: italic; }
+";
+
+/// Colours Lox source by token. Text the scanner rejects is left as it is,
+/// so incomplete input can still be shown.
+pub struct Highlighter {
+    html: bool,
+}
+
+impl Highlighter {
+    /// Output coloured with ANSI escape codes, for terminals.
+    pub fn ansi() -> Highlighter {
+        Highlighter { html: false }
+    }
+
+    /// HTML with each token in a `<span>` with its [`Highlight::class`].
+    pub fn html() -> Highlighter {
+        Highlighter { html: true }
+    }
+
+    pub fn highlight(&self, source: &str) -> String {
+        let mut scanner = Scanner::new(source);
+        let mut tokens = scanner.scan().cloned().collect::<Vec<_>>();
+        tokens.extend(scanner.comments().iter().cloned());
+        tokens.sort_by_key(SyntaxToken::offset);
+
+        let mut out = String::with_capacity(source.len() * 2);
+        let mut last_end = 0;
+        for token in tokens {
+            let span = token.span();
+            self.push(&mut out, &source[last_end..span.start], None);
+            self.push(&mut out, token.text(), Some(Highlight::of(token.kind())));
+            last_end = span.end;
+        }
+        self.push(&mut out, &source[last_end..], None);
+        out
+    }
+
+    /// A standalone HTML page showing `source`.
+    pub fn html_page(title: &str, source: &str) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+             <style>\n{}</style>\n</head>\n<body>\n<pre class=\"lox\">{}</pre>\n</body>\n</html>\n",
+            escape(title),
+            STYLESHEET,
+            Highlighter::html().highlight(source)
+        )
+    }
+
+    fn push(&self, out: &mut String, text: &str, highlight: Option<Highlight>) {
+        if text.is_empty() {
+            return;
+        }
+        if self.html {
+            match highlight {
+                Some(highlight) => {
+                    *out += &format!(
+                        "<span class=\"{}\">{}</span>",
+                        highlight.class(),
+                        escape(text)
+                    )
+                }
+                None => *out += &escape(text),
+            }
+        } else {
+            match highlight.and_then(Highlight::color) {
+                Some(color) => *out += &format!("{}{}{}", color, text, RESET),
+                None => *out += text,
+            }
+        }
+    }
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out += "&amp;",
+            '<' => out += "&lt;",
+            '>' => out += "&gt;",
+            '"' => out += "&quot;",
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Highlighter;
+
+    #[test]
+    fn ansi() {
+        assert_eq!(
+            Highlighter::ansi().highlight("var a = 1; // one\nprint \"x\" @"),
+            "\x1b[35mvar\x1b[0m a = \x1b[36m1\x1b[0m; \x1b[90m// one\x1b[0m\n\
+             \x1b[35mprint\x1b[0m \x1b[32m\"x\"\x1b[0m @"
+        );
+    }
+
+    #[test]
+    fn html() {
+        assert_eq!(
+            Highlighter::html().highlight("print a < \"<b>\";"),
+            "<span class=\"keyword\">print</span> <span class=\"identifier\">a</span> \
+             <span class=\"operator\">&lt;</span> <span class=\"string\">&quot;&lt;b&gt;&quot;</span>\
+             <span class=\"punctuation\">;</span>"
+        );
+        let page = Highlighter::html_page("a & b", "nil");
+        assert!(page.contains("<title>a &amp; b</title>"));
+        assert!(page.contains("<pre class=\"lox\"><span class=\"keyword\">nil</span></pre>"));
+    }
+}
@@ -0,0 +1,93 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+/// A handle to an interned string.
+///
+/// Every distinct text is stored exactly once, so two symbols are equal iff
+/// they share the same allocation. Comparing and hashing a `Symbol` only looks
+/// at the pointer, never at the text.
+#[derive(Clone)]
+pub struct Symbol(Rc<str>);
+
+impl Symbol {
+    pub fn intern(text: &str) -> Symbol {
+        INTERNER.with(|interner| interner.borrow_mut().intern(text))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The shared string behind this symbol, for values that outlive the token.
+    pub fn as_rc(&self) -> Rc<str> {
+        Rc::clone(&self.0)
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Symbol) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.0.as_ptr() as usize).hash(state)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+#[derive(Default)]
+pub struct Interner {
+    strings: HashSet<Rc<str>>,
+}
+
+impl Interner {
+    pub fn intern(&mut self, text: &str) -> Symbol {
+        if let Some(interned) = self.strings.get(text) {
+            return Symbol(Rc::clone(interned));
+        }
+        let interned: Rc<str> = Rc::from(text);
+        self.strings.insert(Rc::clone(&interned));
+        Symbol(interned)
+    }
+}
+
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::new(Interner::default());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Symbol;
+
+    #[test]
+    fn same_text_same_symbol() {
+        let a = Symbol::intern("hello");
+        let b = Symbol::intern(&String::from("hello"));
+        assert_eq!(a, b);
+        assert_eq!(a.as_str().as_ptr(), b.as_str().as_ptr());
+    }
+
+    #[test]
+    fn different_text_different_symbol() {
+        assert_ne!(Symbol::intern("hello"), Symbol::intern("world"));
+        assert_ne!(Symbol::intern(""), Symbol::intern(" "));
+    }
+}
@@ -1,23 +1,198 @@
 use crate::ast::{self, AstNode};
 use crate::env::Environment;
-use crate::green::SyntaxNode;
+use crate::error::{ErrorKind, RuntimeError, StackFrame};
+use crate::green::{NodeOrToken, SyntaxNode, SyntaxToken};
+use crate::intern::Symbol;
 use crate::kinds::SyntaxKind;
+use crate::limits::{Limits, Usage, TIME_CHECK_INTERVAL};
+use crate::line_index::LineIndex;
+use crate::lox;
+use crate::map::{Map, MapKey};
+use crate::module::{FileLoader, Module, ModuleLoader};
+use crate::native::{self, NativeFn, NativeFunction};
+use crate::scanner;
 use crate::value::Value;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::rc::Rc;
+use std::time::Instant;
+
+/// A function call in progress.
+struct CallFrame {
+    function: String,
+    /// Span of the call's closing paren, or `None` if the host made the call.
+    call_site: Option<Range<usize>>,
+}
+
+/// A `break` or `continue` that is leaving statements on its way out to the
+/// innermost loop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Jump {
+    Break,
+    Continue,
+}
 
 pub struct Interpreter {
     env: Environment,
+    frames: Vec<CallFrame>,
+    output: Box<dyn Write>,
+    error_output: Box<dyn Write>,
+    limits: Limits,
+    usage: Usage,
+    /// String literal contents keyed by the literal token's symbol, so the
+    /// surrounding quotes are only stripped the first time it is evaluated.
+    strings: HashMap<Symbol, Rc<str>>,
+    jump: Option<Jump>,
+    /// Lines of the source being run, for the `line` of caught errors.
+    lines: Option<LineIndex>,
+    /// Natives, which every module's globals start with.
+    natives: HashMap<Symbol, Value>,
+    loader: Box<dyn ModuleLoader>,
+    /// Modules loaded so far, by resolved path.
+    modules: HashMap<String, Rc<Module>>,
+    /// The paths of the modules being run, innermost last, starting with
+    /// the script's own if it has one.
+    loading: Vec<String>,
+    /// The names the running module has exported so far.
+    exports: Vec<Symbol>,
 }
 
-impl Interpreter {
-    pub fn default() -> Interpreter {
-        Interpreter {
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        let mut interpreter = Interpreter {
             env: Environment::default(),
+            frames: Vec::new(),
+            output: Box::new(io::stdout()),
+            error_output: Box::new(io::stderr()),
+            limits: Limits::default(),
+            usage: Usage::default(),
+            strings: HashMap::new(),
+            jump: None,
+            lines: None,
+            natives: HashMap::new(),
+            loader: Box::new(FileLoader),
+            modules: HashMap::new(),
+            loading: Vec::new(),
+            exports: Vec::new(),
+        };
+        native::define_builtins(&mut interpreter);
+        interpreter
+    }
+}
+
+impl Interpreter {
+    /// Exposes a Rust function to scripts as the global `name`.
+    pub fn define_native(&mut self, name: &str, arity: usize, function: NativeFn) {
+        let native = Value::NativeFunction(Rc::new(NativeFunction::new(name, arity, function)));
+        self.natives.insert(Symbol::intern(name), native.clone());
+        self.define_global(name, native);
+    }
+
+    /// Defines or overwrites the global variable `name`.
+    pub fn define_global(&mut self, name: &str, value: Value) {
+        self.env.global_mut().define(Symbol::intern(name), value);
+    }
+
+    pub fn global(&self, name: &str) -> Option<Value> {
+        self.env.global().get(&Symbol::intern(name))
+    }
+
+    /// Replaces the sink `print` writes to, stdout by default.
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.output = Box::new(output);
+    }
+
+    /// Replaces the sink for diagnostics, stderr by default.
+    pub fn set_error_output(&mut self, error_output: impl Write + 'static) {
+        self.error_output = Box::new(error_output);
+    }
+
+    pub fn output(&mut self) -> &mut dyn Write {
+        self.output.as_mut()
+    }
+
+    pub fn error_output(&mut self) -> &mut dyn Write {
+        self.error_output.as_mut()
+    }
+
+    /// Sets the limits for the following evaluations and starts measuring
+    /// usage against them from zero.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+        self.reset_usage();
+    }
+
+    /// Starts measuring steps, time and allocations against the limits
+    /// from zero again, e.g. before running the next script.
+    pub fn reset_usage(&mut self) {
+        self.usage = Usage::default();
+    }
+
+    /// Accounts for `bytes` of memory created by the script, failing once the
+    /// allocation limit is exceeded. Natives that build large values should
+    /// report them here too.
+    pub fn allocate(&mut self, bytes: usize) -> Result<(), RuntimeError> {
+        self.usage.allocated = self.usage.allocated.saturating_add(bytes);
+        match self.limits.max_allocation {
+            Some(max) if self.usage.allocated > max => Err(RuntimeError::with_kind(
+                ErrorKind::AllocationLimit,
+                format!("Allocation limit of {} bytes exceeded.", max),
+            )),
+            _ => Ok(()),
         }
     }
 
-    pub fn interpret(&mut self, syntax_node: SyntaxNode) -> Value {
+    fn step(&mut self) -> Result<(), RuntimeError> {
+        self.usage.steps += 1;
+        if let Some(max) = self.limits.max_steps {
+            if self.usage.steps > max {
+                return Err(RuntimeError::with_kind(
+                    ErrorKind::StepLimit,
+                    format!("Step limit of {} exceeded.", max),
+                ));
+            }
+        }
+        let started = *self.usage.started.get_or_insert_with(Instant::now);
+        if let Some(timeout) = self.limits.timeout {
+            if self.usage.steps.is_multiple_of(TIME_CHECK_INTERVAL) && started.elapsed() > timeout {
+                return Err(RuntimeError::with_kind(
+                    ErrorKind::Timeout,
+                    format!("Time limit of {:?} exceeded.", timeout),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces how imported modules are found and read, from the file
+    /// system by default.
+    pub fn set_loader(&mut self, loader: impl ModuleLoader + 'static) {
+        self.loader = Box::new(loader);
+    }
+
+    /// Sets the path of the script about to run, which its imports are
+    /// relative to. Without one they are relative to the working directory.
+    pub fn set_module_path(&mut self, path: &str) {
+        self.loading = vec![self.loader.resolve(None, path)];
+    }
+
+    /// Sets the source the following statements were parsed from, so errors
+    /// caught by scripts can tell which line they were raised on.
+    pub fn set_source(&mut self, source: &str) {
+        self.lines = Some(LineIndex::new(source));
+    }
+
+    pub fn interpret(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
+        let span = syntax_node.span();
+        self.evaluate(syntax_node)
+            .map_err(|error| self.with_backtrace(error, span))
+    }
+
+    fn evaluate(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
+        self.step()?;
         match syntax_node.kind() {
-            SyntaxKind::Literal => self.evaluate_literal(syntax_node),
+            SyntaxKind::Literal => Ok(self.evaluate_literal(syntax_node)),
             SyntaxKind::UnaryExpr => self.evaluate_unary(syntax_node),
             SyntaxKind::BinExpr => self.evaluate_binary(syntax_node),
             SyntaxKind::Print => self.print(syntax_node),
@@ -27,41 +202,332 @@ impl Interpreter {
             SyntaxKind::If => self.if_condition(syntax_node),
             SyntaxKind::And | SyntaxKind::Or => self.logical(syntax_node),
             SyntaxKind::While => self.while_condition(syntax_node),
+            SyntaxKind::For => self.for_loop(syntax_node),
+            SyntaxKind::Break => self.jump(Jump::Break),
+            SyntaxKind::Continue => self.jump(Jump::Continue),
+            SyntaxKind::Throw => self.throw(syntax_node),
+            SyntaxKind::Import => self.import(syntax_node),
+            SyntaxKind::Export => self.export(syntax_node),
+            SyntaxKind::Get => self.get(syntax_node),
+            SyntaxKind::Interpolation => self.interpolation(syntax_node),
+            SyntaxKind::Try => self.try_statement(syntax_node),
             SyntaxKind::Assign => self.assign(syntax_node),
+            SyntaxKind::Call => self.call(syntax_node),
+            SyntaxKind::ListLiteral => self.list(syntax_node),
+            SyntaxKind::MapLiteral => self.map(syntax_node),
+            SyntaxKind::Index => self.index(syntax_node),
+            SyntaxKind::Nil => Ok(Value::Nil),
             _ => panic!("{:?} can not be interpreted", syntax_node.kind()),
         }
     }
 
-    fn assign(&mut self, syntax_node: SyntaxNode) -> Value {
+    fn call(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
+        let call = ast::Call::cast(syntax_node).unwrap();
+        let callee = self.interpret(call.callee())?;
+        let arguments = call
+            .arguments()
+            .map(|argument| self.interpret(argument))
+            .collect::<Result<Vec<Value>, RuntimeError>>()?;
+        let paren = call.paren().span();
+        self.call_function(callee, &arguments, Some(paren.clone()))
+            .map_err(|error| self.with_backtrace(error, Some(paren)))
+    }
+
+    /// Calls a callable value, as a call expression in a script would.
+    pub fn call_value(
+        &mut self,
+        callee: Value,
+        arguments: &[Value],
+    ) -> Result<Value, RuntimeError> {
+        self.call_function(callee, arguments, None)
+            .map_err(|error| self.with_backtrace(error, None))
+    }
+
+    fn call_function(
+        &mut self,
+        callee: Value,
+        arguments: &[Value],
+        call_site: Option<Range<usize>>,
+    ) -> Result<Value, RuntimeError> {
+        match callee {
+            Value::NativeFunction(function) => {
+                if arguments.len() != function.arity() {
+                    return Err(RuntimeError::new(format!(
+                        "Expected {} arguments but got {}.",
+                        function.arity(),
+                        arguments.len()
+                    )));
+                }
+                if let Some(max) = self.limits.max_call_depth {
+                    if self.frames.len() >= max {
+                        return Err(RuntimeError::with_kind(
+                            ErrorKind::CallDepthLimit,
+                            "Stack overflow.",
+                        ));
+                    }
+                }
+                self.frames.push(CallFrame {
+                    function: function.name().to_string(),
+                    call_site,
+                });
+                let result = function
+                    .call(self, arguments)
+                    .map_err(|error| self.with_backtrace(error, None));
+                self.frames.pop();
+                result
+            }
+            _ => Err(RuntimeError::new("Can only call functions and classes.")),
+        }
+    }
+
+    /// Attaches the current call stack to an error that has none yet, with
+    /// `span` as the location in the innermost frame.
+    fn with_backtrace(&self, error: RuntimeError, span: Option<Range<usize>>) -> RuntimeError {
+        if !error.trace().is_empty() {
+            return error;
+        }
+        let mut trace = Vec::new();
+        let mut location = span.clone();
+        for frame in self.frames.iter().rev() {
+            trace.push(StackFrame {
+                function: Some(frame.function.clone()),
+                offset: location.map(|span| span.start),
+            });
+            location = frame.call_site.clone();
+        }
+        trace.push(StackFrame {
+            function: None,
+            offset: location.as_ref().map(|span| span.start),
+        });
+        // A native has no span of its own, so point at the call that failed.
+        let span = span.or_else(|| self.frames.last()?.call_site.clone());
+        error.with_trace(trace, span)
+    }
+
+    fn list(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
+        let list = ast::ListLiteral::cast(syntax_node).unwrap();
+        let elements = list
+            .elements()
+            .map(|element| self.interpret(element))
+            .collect::<Result<Vec<Value>, RuntimeError>>()?;
+        self.allocate(elements.len() * std::mem::size_of::<Value>())?;
+        Ok(Value::list(elements))
+    }
+
+    fn map(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
+        let literal = ast::MapLiteral::cast(syntax_node).unwrap();
+        let mut map = Map::new();
+        for (key, value) in literal.entries() {
+            let key_span = key.span();
+            let key = self.interpret(key)?;
+            let key = MapKey::new(&key).map_err(|error| self.with_backtrace(error, key_span))?;
+            let value = self.interpret(value)?;
+            map.insert(key, value);
+        }
+        self.allocate(map.len() * std::mem::size_of::<(MapKey, Value)>())?;
+        Ok(Value::map(map))
+    }
+
+    fn index(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
+        let index = ast::Index::cast(syntax_node).unwrap();
+        let target = self.interpret(index.target())?;
+        let position = self.interpret(index.index())?;
+        match &target {
+            Value::List(list) => {
+                let list = list.borrow();
+                let position = Self::list_position(&position, list.len())?;
+                Ok(list[position].clone())
+            }
+            Value::Map(map) => {
+                let key = MapKey::new(&position)?;
+                map.borrow()
+                    .get(&key)
+                    .cloned()
+                    .ok_or_else(|| RuntimeError::new(format!("Undefined key '{}'.", position)))
+            }
+            _ => Err(Self::not_indexable()),
+        }
+    }
+
+    fn not_indexable() -> RuntimeError {
+        RuntimeError::new("Only lists and maps can be indexed.")
+    }
+
+    /// Checks that `index` is a whole number that is in bounds for a list of
+    /// length `len`.
+    fn list_position(index: &Value, len: usize) -> Result<usize, RuntimeError> {
+        let index = match index {
+            Value::Number(n) if n.fract() == 0. => *n,
+            _ => return Err(RuntimeError::new("List index must be an integer.")),
+        };
+        if index < 0. || index >= len as f64 {
+            return Err(RuntimeError::new(format!(
+                "Index {} is out of bounds for a list of length {}.",
+                Value::Number(index),
+                len
+            )));
+        }
+        Ok(index as usize)
+    }
+
+    fn assign(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
         let assign = ast::Assign::cast(syntax_node).unwrap();
-        let var_name = assign.var_name();
-        if let Some(_) = self.env.get(&var_name) {
-            let value = self.interpret(assign.value());
-            self.env.assign(&var_name, value);
+        let var_name = match assign.var_name() {
+            Some(var_name) => var_name,
+            None => return self.assign_index(assign),
+        };
+        if self.env.get(&var_name).is_none() {
+            return Err(Self::undefined_variable(&var_name));
         }
-        Value::Nil
+        let value = self.interpret(assign.value())?;
+        self.env.assign(&var_name, value.clone());
+        Ok(value)
     }
 
-    fn while_condition(&mut self, syntax_node: SyntaxNode) -> Value {
+    fn assign_index(&mut self, assign: ast::Assign) -> Result<Value, RuntimeError> {
+        let index = ast::Index::cast(assign.target()).unwrap();
+        let target = self.interpret(index.target())?;
+        let position = self.interpret(index.index())?;
+        let value = self.interpret(assign.value())?;
+        match &target {
+            Value::List(list) => {
+                let mut list = list.borrow_mut();
+                let position = Self::list_position(&position, list.len())?;
+                list[position] = value.clone();
+            }
+            Value::Map(map) => {
+                let key = MapKey::new(&position)?;
+                if map.borrow_mut().insert(key, value.clone()).is_none() {
+                    self.allocate(std::mem::size_of::<(MapKey, Value)>())?;
+                }
+            }
+            _ => return Err(Self::not_indexable()),
+        }
+        Ok(value)
+    }
+
+    fn while_condition(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
         let while_condition = ast::While::cast(syntax_node).unwrap();
-        while Self::is_truthy(&self.interpret(while_condition.condition())) {
-            self.interpret(while_condition.body());
+        while Self::is_truthy(&self.interpret(while_condition.condition())?) {
+            self.interpret(while_condition.body())?;
+            if self.jump.take() == Some(Jump::Break) {
+                break;
+            }
+        }
+        Ok(Value::Nil)
+    }
+
+    fn for_loop(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
+        let for_loop = ast::For::cast(syntax_node).unwrap();
+        self.env = Environment::new(self.env.clone());
+        let result = self.run_for(&for_loop);
+        self.env = self.env.enclosing().unwrap();
+        result.map(|_| Value::Nil)
+    }
+
+    fn run_for(&mut self, for_loop: &ast::For) -> Result<(), RuntimeError> {
+        if let Some(initializer) = for_loop.initializer() {
+            self.interpret(initializer)?;
+        }
+        loop {
+            if let Some(condition) = for_loop.condition() {
+                if !Self::is_truthy(&self.interpret(condition)?) {
+                    return Ok(());
+                }
+            }
+            self.interpret(for_loop.body())?;
+            if self.jump.take() == Some(Jump::Break) {
+                return Ok(());
+            }
+            if let Some(increment) = for_loop.increment() {
+                self.interpret(increment)?;
+            }
+        }
+    }
+
+    fn throw(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
+        let throw = ast::Throw::cast(syntax_node).unwrap();
+        let value = self.interpret(throw.expr())?;
+        Err(RuntimeError::thrown(value))
+    }
+
+    /// Runs the `finally` block however the rest of the statement ends. If
+    /// the block itself fails or jumps, that replaces the error or jump it
+    /// interrupted.
+    ///
+    /// Exceeding a limit must stop the script, so those errors are neither
+    /// caught nor run `finally` blocks.
+    fn try_statement(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
+        let try_statement = ast::Try::cast(syntax_node).unwrap();
+        let mut result = self.interpret(try_statement.body());
+        if let Some(catch) = try_statement.catch_clause() {
+            let caught = match &result {
+                Err(error) if error.kind() == ErrorKind::Script => Some(self.error_value(error)),
+                _ => None,
+            };
+            if let Some(value) = caught {
+                result = self.catch(catch, value);
+            }
+        }
+        if let Err(error) = &result {
+            if error.kind() != ErrorKind::Script {
+                return result;
+            }
+        }
+        if let Some(finally) = try_statement.finally_block() {
+            let pending = self.jump.take();
+            self.interpret(finally)?;
+            if self.jump.is_some() {
+                return Ok(Value::Nil);
+            }
+            self.jump = pending;
+        }
+        result.map(|_| Value::Nil)
+    }
+
+    fn catch(&mut self, catch: ast::Catch, value: Value) -> Result<Value, RuntimeError> {
+        self.env = Environment::new(self.env.clone());
+        self.env.define(catch.name().symbol(), value);
+        let result = self.interpret(catch.body());
+        self.env = self.env.enclosing().unwrap();
+        result
+    }
+
+    /// What a `catch` clause receives for `error`: the thrown value, or for
+    /// an error raised by the interpreter or a native, a map of its
+    /// `message` and `line`.
+    fn error_value(&self, error: &RuntimeError) -> Value {
+        if let Some(value) = error.value() {
+            return value.clone();
         }
-        Value::Nil
+        let line = match (&self.lines, error.span()) {
+            (Some(lines), Some(span)) => Value::Number(lines.line(span.start) as f64),
+            _ => Value::Nil,
+        };
+        let mut map = Map::new();
+        let message = Value::String(error.message().into());
+        map.insert(MapKey::String("message".into()), message);
+        map.insert(MapKey::String("line".into()), line);
+        Value::map(map)
     }
 
-    fn logical(&mut self, syntax_node: SyntaxNode) -> Value {
+    fn jump(&mut self, jump: Jump) -> Result<Value, RuntimeError> {
+        self.jump = Some(jump);
+        Ok(Value::Nil)
+    }
+
+    fn logical(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
         let logical = ast::Logical::cast(syntax_node).unwrap();
-        let left_val = self.interpret(logical.left());
+        let left_val = self.interpret(logical.left())?;
         match logical.kind() {
             SyntaxKind::And => {
                 if !Self::is_truthy(&left_val) {
-                    return left_val;
+                    return Ok(left_val);
                 }
             }
             SyntaxKind::Or => {
                 if Self::is_truthy(&left_val) {
-                    return left_val;
+                    return Ok(left_val);
                 }
             }
             _ => panic!("Unexpected logical kind: {:?}", logical.kind()),
@@ -69,57 +535,187 @@ impl Interpreter {
         self.interpret(logical.right())
     }
 
-    fn if_condition(&mut self, syntax_node: SyntaxNode) -> Value {
+    fn if_condition(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
         let if_condition = ast::If::cast(syntax_node).unwrap();
-        let condition = self.interpret(if_condition.condition());
-        if let Value::Bool(true) = condition {
-            self.interpret(if_condition.then_branch());
+        let condition = self.interpret(if_condition.condition())?;
+        if Self::is_truthy(&condition) {
+            self.interpret(if_condition.then_branch())?;
         } else if let Some(else_branch) = if_condition.else_branch() {
-            self.interpret(else_branch);
+            self.interpret(else_branch)?;
         }
-        Value::Nil
+        Ok(Value::Nil)
     }
 
-    fn block(&mut self, syntax_node: SyntaxNode) -> Value {
+    fn block(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
         self.env = Environment::new(self.env.clone());
         let block = ast::Block::cast(syntax_node).unwrap();
-        for child in block.children() {
-            self.interpret(child);
-        }
+        let result = block.children().try_for_each(|child| match self.jump {
+            Some(_) => Ok(()),
+            None => self.interpret(child).map(drop),
+        });
         self.env = self.env.enclosing().unwrap();
-        Value::Nil
+        result.map(|_| Value::Nil)
     }
 
-    fn identifier(&mut self, syntax_node: SyntaxNode) -> Value {
+    fn identifier(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
         let ident = ast::Identifier::cast(syntax_node).unwrap();
-        let value = self
-            .env
-            .get(&ident.name())
-            .expect(&format!("undefind variable {}", ident.name()));
-        value
+        let name = ident.name();
+        self.env
+            .get(&name)
+            .ok_or_else(|| Self::undefined_variable(&name))
     }
 
-    fn var_declaration(&mut self, syntax_node: SyntaxNode) -> Value {
+    fn undefined_variable(name: &Symbol) -> RuntimeError {
+        RuntimeError::new(format!("Undefined variable '{}'.", name))
+    }
+
+    fn var_declaration(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
         let var_declaration = ast::VarDeclaration::cast(syntax_node).unwrap();
         let ident = var_declaration.ident();
-        let initial_value = self.interpret(var_declaration.initializer());
-        self.env.define(ident.text(), initial_value);
-        Value::Nil
+        let initial_value = self.interpret(var_declaration.initializer())?;
+        self.env.define(ident.symbol(), initial_value);
+        Ok(Value::Nil)
+    }
+
+    fn import(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
+        let import = ast::Import::cast(syntax_node).unwrap();
+        let path = import.path();
+        let module = self.load_module(&scanner::string_value(&path))?;
+        self.env
+            .define(import.name().symbol(), Value::Module(module));
+        Ok(Value::Nil)
     }
 
-    fn print(&mut self, syntax_node: SyntaxNode) -> Value {
+    /// Runs the module at `path`, relative to the running module, with
+    /// globals of its own, or returns it from the cache if it already ran.
+    /// Errors in the module are reported with their line in it.
+    fn load_module(&mut self, path: &str) -> Result<Rc<Module>, RuntimeError> {
+        let path = self
+            .loader
+            .resolve(self.loading.last().map(String::as_str), path);
+        if let Some(module) = self.modules.get(&path) {
+            return Ok(Rc::clone(module));
+        }
+        if let Some(start) = self.loading.iter().position(|loading| *loading == path) {
+            let mut cycle = self.loading[start..].to_vec();
+            cycle.push(path);
+            return Err(RuntimeError::new(format!(
+                "Import cycle: {}.",
+                cycle.join(" -> ")
+            )));
+        }
+        let source = self
+            .loader
+            .load(&path)
+            .map_err(|err| RuntimeError::new(format!("Can't load module '{}': {}.", path, err)))?;
+        let lines = LineIndex::new(&source);
+        let statements = lox::parse(&source).map_err(|errors| {
+            let error = &errors[0];
+            let line = lines.line(error.offset());
+            RuntimeError::new(format!("{} [line {} of '{}']", error, line, path))
+        })?;
+
+        let mut globals = Environment::default();
+        for (name, native) in &self.natives {
+            globals.define(name.clone(), native.clone());
+        }
+        let env = std::mem::replace(&mut self.env, globals);
+        let lines = self.lines.replace(lines);
+        let exports = std::mem::take(&mut self.exports);
+        self.loading.push(path);
+        let result = statements
+            .into_iter()
+            .try_for_each(|statement| self.interpret(statement).map(drop));
+        let path = self.loading.pop().unwrap();
+        let exports = std::mem::replace(&mut self.exports, exports);
+        let lines = std::mem::replace(&mut self.lines, lines).unwrap();
+        let globals = std::mem::replace(&mut self.env, env);
+
+        if let Err(error) = result {
+            let line = error.span().map(|span| lines.line(span.start));
+            return Err(error.in_module(&path, line));
+        }
+        let exports = exports
+            .into_iter()
+            .map(|name| {
+                let value = globals.get(&name).unwrap_or(Value::Nil);
+                (name, value)
+            })
+            .collect();
+        let module = Rc::new(Module::new(path.clone(), exports));
+        self.modules.insert(path, Rc::clone(&module));
+        Ok(module)
+    }
+
+    fn export(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
+        let declaration = ast::Export::cast(syntax_node).unwrap().declaration();
+        let name = declaration.ident().symbol();
+        self.var_declaration(declaration.syntax().clone())?;
+        self.exports.push(name);
+        Ok(Value::Nil)
+    }
+
+    fn get(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
+        let get = ast::Get::cast(syntax_node).unwrap();
+        let object = self.interpret(get.object())?;
+        let name = get.name();
+        match &object {
+            Value::Module(module) => module.get(name.text()).ok_or_else(|| {
+                RuntimeError::new(format!(
+                    "Module '{}' has no export '{}'.",
+                    module.path(),
+                    name
+                ))
+            }),
+            Value::Map(map) => map
+                .borrow()
+                .get(&MapKey::String(name.text().into()))
+                .cloned()
+                .ok_or_else(|| RuntimeError::new(format!("Undefined key '{}'.", name))),
+            _ => Err(RuntimeError::new("Only modules and maps have properties.")),
+        }
+    }
+
+    fn interpolation(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
+        let mut text = String::new();
+        for part in syntax_node.children() {
+            match part {
+                NodeOrToken::Token(token) => text += &scanner::string_value(&token),
+                NodeOrToken::Node(expr) => text += &self.interpret(expr)?.to_string(),
+            }
+        }
+        self.allocate(text.len())?;
+        Ok(Value::String(text.into()))
+    }
+
+    fn print(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
         let print = ast::Print::cast(syntax_node).unwrap();
-        let value = self.interpret(print.expr());
-        println!("{}", value);
-        Value::Nil
+        let value = self.interpret(print.expr())?;
+        writeln!(self.output, "{}", value)
+            .map_err(|err| RuntimeError::new(format!("Could not write output: {}.", err)))?;
+        Ok(Value::Nil)
     }
 
-    fn evaluate_binary(&mut self, syntax_node: SyntaxNode) -> Value {
+    fn evaluate_binary(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
         assert_eq!(syntax_node.kind(), SyntaxKind::BinExpr);
-        let bin_expr = ast::BinExpr::cast(syntax_node.clone()).unwrap();
-        let left_val = self.interpret(bin_expr.left());
-        let right_val = self.interpret(bin_expr.right());
-        match (&left_val, bin_expr.op().kind(), &right_val) {
+        let bin_expr = ast::BinExpr::cast(syntax_node).unwrap();
+        let left_val = self.interpret(bin_expr.left())?;
+        let right_val = self.interpret(bin_expr.right())?;
+        let op = bin_expr.op().kind();
+        let value = Self::binary(&left_val, op.clone(), &right_val).ok_or_else(|| match op {
+            SyntaxKind::Plus => RuntimeError::new("Operands must be two numbers or two strings."),
+            _ => RuntimeError::new("Operands must be numbers."),
+        })?;
+        if let Value::String(s) = &value {
+            self.allocate(s.len())?;
+        }
+        Ok(value)
+    }
+
+    /// Applies a binary operator to two evaluated operands, or returns `None`
+    /// if the operand types are not supported by the operator.
+    pub(crate) fn binary(left: &Value, op: SyntaxKind, right: &Value) -> Option<Value> {
+        let value = match (left, op, right) {
             (Value::Number(left), SyntaxKind::Plus, Value::Number(right)) => {
                 Value::Number(left + right)
             }
@@ -145,46 +741,62 @@ impl Interpreter {
                 Value::Bool(left <= right)
             }
             (Value::String(left), SyntaxKind::Plus, Value::String(right)) => {
-                Value::String(left.to_string() + right)
+                Value::String(format!("{}{}", left, right).into())
             }
-            (_, SyntaxKind::EqualEqual, _) => Value::Bool(left_val == right_val),
-            (_, SyntaxKind::BangEqual, _) => Value::Bool(left_val != right_val),
-            _ => panic!("Invalid Binary Expr: {}", syntax_node),
-        }
+            (_, SyntaxKind::EqualEqual, _) => Value::Bool(left == right),
+            (_, SyntaxKind::BangEqual, _) => Value::Bool(left != right),
+            _ => return None,
+        };
+        Some(value)
     }
 
-    fn evaluate_unary(&mut self, syntax_node: SyntaxNode) -> Value {
+    fn evaluate_unary(&mut self, syntax_node: SyntaxNode) -> Result<Value, RuntimeError> {
         assert_eq!(syntax_node.kind(), SyntaxKind::UnaryExpr);
-        let unary_expr = ast::UnaryExpr::cast(syntax_node.clone()).unwrap();
-        let value = self.interpret(unary_expr.node());
-        match (unary_expr.op().kind(), &value) {
-            (SyntaxKind::Minus, Value::Number(n)) => Value::Number(-n),
-            (SyntaxKind::Bang, _) => Value::Bool(!Self::is_truthy(&value)),
-            _ => panic!("Invalid Unary Expr: {}", syntax_node),
+        let unary_expr = ast::UnaryExpr::cast(syntax_node).unwrap();
+        let value = self.interpret(unary_expr.node())?;
+        Self::unary(unary_expr.op().kind(), &value)
+            .ok_or_else(|| RuntimeError::new("Operand must be a number."))
+    }
+
+    /// Applies a unary operator to an evaluated operand, or returns `None` if
+    /// the operand type is not supported by the operator.
+    pub(crate) fn unary(op: SyntaxKind, value: &Value) -> Option<Value> {
+        match (op, value) {
+            (SyntaxKind::Minus, Value::Number(n)) => Some(Value::Number(-n)),
+            (SyntaxKind::Bang, _) => Some(Value::Bool(!Self::is_truthy(value))),
+            _ => None,
         }
     }
 
-    fn evaluate_literal(&self, syntax_node: SyntaxNode) -> Value {
+    fn evaluate_literal(&mut self, syntax_node: SyntaxNode) -> Value {
         assert_eq!(syntax_node.kind(), SyntaxKind::Literal);
         let literal = ast::Literal::cast(syntax_node).unwrap();
         let token = literal.token();
+        if token.kind() == SyntaxKind::String {
+            let text =
+                self.strings
+                    .entry(token.symbol())
+                    .or_insert_with(|| match Self::literal(&token) {
+                        Value::String(text) => text,
+                        _ => unreachable!(),
+                    });
+            return Value::String(Rc::clone(text));
+        }
+        Self::literal(&token)
+    }
+
+    pub(crate) fn literal(token: &SyntaxToken) -> Value {
         match token.kind() {
             SyntaxKind::False => Value::Bool(false),
             SyntaxKind::True => Value::Bool(true),
-            SyntaxKind::String => {
-                let text = token.text().chars().filter(|c| *c != '\"').collect();
-                Value::String(text)
-            }
-            SyntaxKind::Number => {
-                let number = token.text().parse::<f32>().unwrap();
-                Value::Number(number)
-            }
+            SyntaxKind::String => Value::String(scanner::string_value(token).into()),
+            SyntaxKind::Number => Value::Number(scanner::number_value(token)),
             SyntaxKind::Nil => Value::Nil,
             _ => panic!("Unexpected token: {:?}", token),
         }
     }
 
-    fn is_truthy(val: &Value) -> bool {
+    pub(crate) fn is_truthy(val: &Value) -> bool {
         match val {
             Value::Nil => false,
             Value::Bool(b) => *b,
@@ -198,23 +810,45 @@ mod tests {
     use super::Interpreter;
     use super::Value;
     use crate::Parser;
+    use crate::RuntimeError;
     use crate::Scanner;
+    use crate::SharedBuffer;
+    use crate::{ErrorKind, Limits, LineIndex};
+    use std::time::Duration;
 
-    fn check_interpret(source: &str, expected: Value) {
+    fn run(interpreter: &mut Interpreter, source: &str) -> Result<Value, RuntimeError> {
         let mut scanner = Scanner::new(source);
         let tokens = scanner.scan().cloned().collect();
         let mut parser = Parser::new(tokens);
-        let mut stmts = parser.parse();
+        let mut value = Value::Nil;
+        for stmt in parser.parse().unwrap() {
+            value = interpreter.interpret(stmt)?;
+        }
+        Ok(value)
+    }
+
+    fn check_error(source: &str, message: &str) {
+        let mut interpreter = Interpreter::default();
+        let error = run(&mut interpreter, source).unwrap_err();
+        assert_eq!(error.message(), message);
+    }
+
+    fn check_interpret(source: &str, expected: Value) {
+        let source = format!("{};", source);
+        let mut scanner = Scanner::new(&source);
+        let tokens = scanner.scan().cloned().collect();
+        let mut parser = Parser::new(tokens);
+        let stmts = parser.parse().unwrap();
         let mut interpreter = Interpreter::default();
-        let value = interpreter.interpret(stmts.next().unwrap().clone());
-        assert_eq!(value, expected);
+        let value = interpreter.interpret(stmts[0].clone());
+        assert_eq!(value, Ok(expected));
     }
 
     #[test]
     fn literal() {
         check_interpret("true", Value::Bool(true));
         check_interpret("false", Value::Bool(false));
-        check_interpret("\"hello\"", Value::String("hello".to_string()));
+        check_interpret("\"hello\"", Value::String("hello".into()));
         check_interpret("nil", Value::Nil);
     }
 
@@ -242,7 +876,190 @@ mod tests {
         check_interpret("1 != 2", Value::Bool(true));
         check_interpret(
             "\"hello \" + \"world\"",
-            Value::String("hello world".to_string()),
+            Value::String("hello world".into()),
+        );
+    }
+
+    #[test]
+    fn string_equality() {
+        check_interpret("\"hello\" == \"hello\"", Value::Bool(true));
+        check_interpret("\"hel\" + \"lo\" == \"hello\"", Value::Bool(true));
+        check_interpret("\"hello\" != \"world\"", Value::Bool(true));
+    }
+
+    #[test]
+    fn number_precision() {
+        check_interpret("16777217", Value::Number(16777217.));
+        check_interpret("16777216 + 1", Value::Number(16777217.));
+        check_interpret("0.1 + 0.2", Value::Number(0.30000000000000004));
+        check_interpret("0.1 + 0.2 == 0.3", Value::Bool(false));
+        check_interpret("1 / 3", Value::Number(1. / 3.));
+    }
+
+    #[test]
+    fn runtime_errors() {
+        check_error("1 + \"a\";", "Operands must be two numbers or two strings.");
+        check_error("1 < \"a\";", "Operands must be numbers.");
+        check_error("-\"a\";", "Operand must be a number.");
+        check_error("a;", "Undefined variable 'a'.");
+        check_error("a = 1;", "Undefined variable 'a'.");
+        check_error(
+            "{ var a = 1; a + nil; }",
+            "Operands must be two numbers or two strings.",
+        );
+    }
+
+    fn add(_: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+        match (&args[0], &args[1]) {
+            (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a + b)),
+            _ => Err(RuntimeError::new("add expects numbers.")),
+        }
+    }
+
+    #[test]
+    fn native_functions() {
+        let mut interpreter = Interpreter::default();
+        assert_eq!(run(&mut interpreter, "clock() > 0;"), Ok(Value::Bool(true)));
+
+        interpreter.define_native("add", 2, add);
+        assert_eq!(
+            run(&mut interpreter, "add(1, add(2, 3));"),
+            Ok(Value::Number(6.))
         );
+        assert_eq!(run(&mut interpreter, "add == add;"), Ok(Value::Bool(true)));
+        let error = run(&mut interpreter, "add(1, nil);").unwrap_err();
+        assert_eq!(error.message(), "add expects numbers.");
+        let error = run(&mut interpreter, "add(1);").unwrap_err();
+        assert_eq!(error.message(), "Expected 2 arguments but got 1.");
+        check_error("\"clock\"();", "Can only call functions and classes.");
+    }
+
+    #[test]
+    fn stack_traces() {
+        let source = "var a = 1;\nadd(a,\n  add(2, nil));";
+        let mut interpreter = Interpreter::default();
+        interpreter.define_native("add", 2, add);
+        let error = run(&mut interpreter, source).unwrap_err();
+        assert_eq!(
+            error.backtrace(&LineIndex::new(source)),
+            "[native] in add()\n[line 3] in script"
+        );
+
+        let source = "print 1;\nprint -nil;";
+        let error = run(&mut Interpreter::default(), source).unwrap_err();
+        assert_eq!(error.trace().len(), 1);
+        assert_eq!(
+            error.backtrace(&LineIndex::new(source)),
+            "[line 2] in script"
+        );
+    }
+
+    fn warn(interpreter: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+        writeln!(interpreter.error_output(), "warning: {}", args[0]).unwrap();
+        Ok(Value::Nil)
+    }
+
+    #[test]
+    fn output_sinks() {
+        let output = SharedBuffer::new();
+        let error_output = SharedBuffer::new();
+        let mut interpreter = Interpreter::default();
+        interpreter.set_output(output.clone());
+        interpreter.set_error_output(error_output.clone());
+        interpreter.define_native("warn", 1, warn);
+
+        run(
+            &mut interpreter,
+            "print 1; var a = \"two\"; { print a; } warn(3);",
+        )
+        .unwrap();
+        assert_eq!(output.contents(), "1\ntwo\n");
+        assert_eq!(error_output.contents(), "warning: 3\n");
+    }
+
+    fn check_limit(limits: Limits, source: &str, kind: ErrorKind) {
+        let mut interpreter = Interpreter::default();
+        interpreter.set_limits(limits);
+        let error = run(&mut interpreter, source).unwrap_err();
+        assert_eq!(error.kind(), kind, "{}", error);
+
+        // Usage starts from zero again for the next script.
+        interpreter.reset_usage();
+        assert_eq!(run(&mut interpreter, "1 + 2;"), Ok(Value::Number(3.)));
+    }
+
+    #[test]
+    fn step_limit() {
+        let limits = Limits {
+            max_steps: Some(1000),
+            ..Limits::default()
+        };
+        check_limit(limits, "while (true) {}", ErrorKind::StepLimit);
+        // Scripts can't catch their way past a limit.
+        check_limit(
+            limits,
+            "while (true) try { while (true) {} } catch (e) {} finally { continue; }",
+            ErrorKind::StepLimit,
+        );
+    }
+
+    #[test]
+    fn timeout() {
+        let limits = Limits {
+            timeout: Some(Duration::from_millis(10)),
+            ..Limits::default()
+        };
+        check_limit(
+            limits,
+            "var i = 0; while (true) i = i + 1;",
+            ErrorKind::Timeout,
+        );
+
+        // The clock starts with the run, not when the limits are set.
+        let mut interpreter = Interpreter::default();
+        interpreter.set_limits(limits);
+        std::thread::sleep(Duration::from_millis(20));
+        let source = "var i = 0; while (i < 200) i = i + 1;";
+        assert_eq!(run(&mut interpreter, source), Ok(Value::Nil));
+    }
+
+    #[test]
+    fn allocation_limit() {
+        let limits = Limits {
+            max_allocation: Some(1024),
+            ..Limits::default()
+        };
+        check_limit(
+            limits,
+            "var s = \"ab\"; while (true) s = s + s;",
+            ErrorKind::AllocationLimit,
+        );
+    }
+
+    fn recurse(interpreter: &mut Interpreter, _: &[Value]) -> Result<Value, RuntimeError> {
+        let recurse = interpreter.global("recurse").unwrap();
+        interpreter.call_value(recurse, &[])
+    }
+
+    #[test]
+    fn call_depth_limit() {
+        let limits = Limits {
+            max_call_depth: Some(64),
+            ..Limits::default()
+        };
+        let mut interpreter = Interpreter::default();
+        interpreter.set_limits(limits);
+        interpreter.define_native("recurse", 0, recurse);
+        let error = run(&mut interpreter, "recurse();").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::CallDepthLimit);
+        assert_eq!(error.message(), "Stack overflow.");
+        assert_eq!(run(&mut interpreter, "clock() > 0;"), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn unlimited_by_default() {
+        let mut interpreter = Interpreter::default();
+        let source = "var i = 0; var s = \"\"; while (i < 2000) { i = i + 1; s = s + \"x\"; }";
+        assert_eq!(run(&mut interpreter, source), Ok(Value::Nil));
     }
 }
@@ -5,7 +5,10 @@ pub enum SyntaxKind {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
+    Colon,
     Dot,
     Minus,
     Plus,
@@ -27,6 +30,11 @@ pub enum SyntaxKind {
     Identifier,
     String,
     Number,
+    // The pieces of a string with `${}` interpolations: `"...${`,
+    // `}...${` and `}..."`.
+    StringHead,
+    StringMiddle,
+    StringTail,
 
     // Keywords.
     And,
@@ -45,6 +53,17 @@ pub enum SyntaxKind {
     True,
     Var,
     While,
+    Break,
+    Continue,
+    Throw,
+    Try,
+    Catch,
+    Finally,
+    Import,
+    Export,
+    As,
+    Comment,
+    DocComment,
     // Eof,
 
     // Node
@@ -54,4 +73,11 @@ pub enum SyntaxKind {
     Block,
     Logical,
     Assign,
+    Call,
+    ListLiteral,
+    Index,
+    MapLiteral,
+    Get,
+    Interpolation,
+    Doc,
 }
@@ -3,10 +3,51 @@ pub use green::{SyntaxElement, SyntaxNode, SyntaxToken};
 mod kinds;
 mod parser;
 pub use parser::Parser;
+mod resolver;
+pub use resolver::Resolver;
 mod scanner;
 pub use scanner::Scanner;
 mod ast;
 mod interpreter;
 pub use interpreter::Interpreter;
+mod convert;
+pub use convert::{FromLox, IntoLox};
+mod diagnostic;
+pub use diagnostic::{Diagnostic, Label, Renderer, Severity};
+mod doc;
+pub use doc::{DocFormat, DocGenerator};
 mod env;
+mod formatter;
+pub use formatter::Formatter;
+mod error;
+pub use error::{ErrorKind, LoxError, RuntimeError, StackFrame, SyntaxError};
+mod highlight;
+pub use highlight::{Highlight, Highlighter, STYLESHEET};
+mod intern;
+mod json;
+mod limits;
+pub use limits::Limits;
+mod lint;
+pub use lint::{LintConfig, Linter, Rule, RULES};
+mod line_index;
+pub use line_index::LineIndex;
+mod optimizer;
+pub use optimizer::Optimizer;
+mod rename;
+pub use rename::{rename, RenameError, TextEdit};
+mod lox;
+pub use lox::Lox;
+mod lsp;
+pub use lsp::LanguageServer;
+mod map;
+pub use map::{Map, MapKey};
+mod module;
+pub use module::{FileLoader, MemoryLoader, Module, ModuleLoader};
+mod native;
+pub use native::{NativeFn, NativeFunction};
+mod output;
+pub use output::SharedBuffer;
+mod symbols;
+pub use symbols::{Declaration, DeclarationKind, Reference, Scope, SymbolTable};
 mod value;
+pub use value::Value;
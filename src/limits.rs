@@ -0,0 +1,32 @@
+use std::time::{Duration, Instant};
+
+/// Resource limits for running untrusted scripts. `None` means unlimited,
+/// which is the default for every limit.
+///
+/// How deeply a script may nest is always limited, by the parser.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Limits {
+    /// Maximum number of syntax nodes evaluated.
+    pub max_steps: Option<u64>,
+    /// Maximum number of nested function calls.
+    pub max_call_depth: Option<usize>,
+    /// Maximum wall-clock time, from the first step of a run. The clock is
+    /// only checked periodically, so a run can overshoot it by the time a few
+    /// thousand steps take.
+    pub timeout: Option<Duration>,
+    /// Maximum total bytes of strings and collections created at runtime.
+    pub max_allocation: Option<usize>,
+}
+
+/// How many steps run between two checks of the clock.
+pub(crate) const TIME_CHECK_INTERVAL: u64 = 1024;
+
+/// What a run has consumed so far, measured against [`Limits`].
+#[derive(Default)]
+pub(crate) struct Usage {
+    pub steps: u64,
+    pub allocated: usize,
+    /// When the first step was taken, so time spent setting up or parsing
+    /// before the run doesn't count.
+    pub started: Option<Instant>,
+}
@@ -0,0 +1,581 @@
+use crate::ast::{self, AstNode};
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::error::SyntaxError;
+use crate::green::{SyntaxElement, SyntaxNode, SyntaxToken};
+use crate::intern::Symbol;
+use crate::interpreter::Interpreter;
+use crate::kinds::SyntaxKind;
+use crate::optimizer::Optimizer;
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// A check the [`Linter`] can run.
+#[derive(Debug)]
+pub struct Rule {
+    pub id: &'static str,
+    pub description: &'static str,
+    /// The severity used unless the config says otherwise.
+    pub severity: Severity,
+}
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "unused-variable",
+        description: "a local variable is never read or assigned",
+        severity: Severity::Warning,
+    },
+    Rule {
+        id: "unused-assignment",
+        description: "a local variable is assigned but never read",
+        severity: Severity::Warning,
+    },
+    Rule {
+        id: "shadowing",
+        description: "a local variable hides a variable from an outer scope",
+        severity: Severity::Warning,
+    },
+    Rule {
+        id: "unreachable-code",
+        description: "a statement can never run",
+        severity: Severity::Warning,
+    },
+    Rule {
+        id: "constant-condition",
+        description: "an `if` or `while` condition never changes",
+        severity: Severity::Warning,
+    },
+    Rule {
+        id: "self-assignment",
+        description: "a variable is assigned to itself",
+        severity: Severity::Warning,
+    },
+    Rule {
+        id: "nil-comparison",
+        description: "`==` or `!=` compares `nil` with a value that can never be `nil`",
+        severity: Severity::Warning,
+    },
+];
+
+/// Which rules are enabled, and at what severity.
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+    levels: HashMap<&'static str, Option<Severity>>,
+}
+
+impl Default for LintConfig {
+    fn default() -> LintConfig {
+        LintConfig {
+            levels: RULES
+                .iter()
+                .map(|rule| (rule.id, Some(rule.severity)))
+                .collect(),
+        }
+    }
+}
+
+impl LintConfig {
+    /// Reads settings like `shadowing = off`, one per line, on top of the
+    /// defaults. The level is `off`, `warning` or `error`, and `#` starts a
+    /// comment.
+    pub fn parse(text: &str) -> Result<LintConfig, String> {
+        let mut config = LintConfig::default();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", index + 1, message);
+            let (rule, level) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `rule = level`".to_string()))?;
+            let level = match level.trim() {
+                "off" => None,
+                "warning" => Some(Severity::Warning),
+                "error" => Some(Severity::Error),
+                level => return Err(error(format!("unknown level `{}`", level))),
+            };
+            config.set(rule.trim(), level).map_err(error)?;
+        }
+        Ok(config)
+    }
+
+    /// Sets the severity of a rule, or turns it off with `None`.
+    pub fn set(&mut self, rule: &str, level: Option<Severity>) -> Result<(), String> {
+        let rule = RULES
+            .iter()
+            .find(|known| known.id == rule)
+            .ok_or_else(|| format!("unknown rule `{}`", rule))?;
+        self.levels.insert(rule.id, level);
+        Ok(())
+    }
+
+    pub fn severity(&self, rule: &str) -> Option<Severity> {
+        self.levels.get(rule).copied().flatten()
+    }
+}
+
+/// Reports suspicious code that is still valid Lox, as diagnostics coded
+/// with the id of the rule that found them.
+#[derive(Default)]
+pub struct Linter {
+    config: LintConfig,
+}
+
+impl Linter {
+    pub fn new(config: LintConfig) -> Linter {
+        Linter { config }
+    }
+
+    pub fn lint(&self, source: &str) -> Result<Vec<Diagnostic>, Vec<SyntaxError>> {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan().cloned().collect();
+        if !scanner.errors().is_empty() {
+            return Err(scanner.errors().to_vec());
+        }
+        let statements = Parser::new(tokens).parse()?;
+
+        let mut walker = Walker {
+            config: &self.config,
+            scopes: vec![Vec::new()],
+            diagnostics: Vec::new(),
+        };
+        walker.statements(&statements);
+        let mut diagnostics = walker.diagnostics;
+        diagnostics.sort_by_key(|diagnostic| diagnostic.primary().map(|label| label.span.start));
+        Ok(diagnostics)
+    }
+}
+
+struct Local {
+    name: Symbol,
+    declaration: SyntaxToken,
+    reads: usize,
+    writes: usize,
+}
+
+struct Walker<'a> {
+    config: &'a LintConfig,
+    /// Declared variables, innermost scope last. The first scope holds the
+    /// globals, which a host may read, so they are never reported unused.
+    scopes: Vec<Vec<Local>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Walker<'_> {
+    fn report(
+        &mut self,
+        rule: &'static str,
+        message: String,
+        span: Range<usize>,
+        label: Option<(Range<usize>, &str)>,
+    ) {
+        let severity = match self.config.severity(rule) {
+            Some(severity) => severity,
+            None => return,
+        };
+        let mut diagnostic = Diagnostic::new(severity, message)
+            .with_code(rule)
+            .with_primary(span, "");
+        if let Some((span, message)) = label {
+            diagnostic = diagnostic.with_label(span, message);
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn statements(&mut self, statements: &[SyntaxNode]) {
+        let mut diverged_at: Option<SyntaxNode> = None;
+        let mut reported = false;
+        for statement in statements {
+            if let (Some(diverging), false) = (&diverged_at, reported) {
+                let label = diverging
+                    .span()
+                    .map(|span| (span, "any code following this statement is unreachable"));
+                let span = statement.span().unwrap_or(0..0);
+                self.report(
+                    "unreachable-code",
+                    "Unreachable statement.".to_string(),
+                    span,
+                    label,
+                );
+                reported = true;
+            }
+            self.node(statement);
+            if diverged_at.is_none() && Self::never_completes(statement) {
+                diverged_at = Some(statement.clone());
+            }
+        }
+    }
+
+    fn node(&mut self, syntax_node: &SyntaxNode) {
+        match syntax_node.kind() {
+            SyntaxKind::Block => {
+                self.scopes.push(Vec::new());
+                let block = ast::Block::cast(syntax_node.clone()).unwrap();
+                self.statements(&block.children().collect::<Vec<_>>());
+                self.pop_scope();
+            }
+            SyntaxKind::Var => {
+                let var_declaration = ast::VarDeclaration::cast(syntax_node.clone()).unwrap();
+                self.node(&var_declaration.initializer());
+                self.declare(var_declaration.ident());
+            }
+            SyntaxKind::Identifier => {
+                let name = ast::Identifier::cast(syntax_node.clone()).unwrap().name();
+                if let Some(local) = self.lookup(&name) {
+                    local.reads += 1;
+                }
+            }
+            SyntaxKind::Assign => self.assign(syntax_node),
+            SyntaxKind::If => {
+                let if_condition = ast::If::cast(syntax_node.clone()).unwrap();
+                self.condition(&if_condition.condition(), false);
+                self.children(syntax_node);
+            }
+            SyntaxKind::While => {
+                let while_condition = ast::While::cast(syntax_node.clone()).unwrap();
+                self.condition(&while_condition.condition(), true);
+                self.children(syntax_node);
+            }
+            SyntaxKind::For => {
+                let for_loop = ast::For::cast(syntax_node.clone()).unwrap();
+                if let Some(condition) = for_loop.condition() {
+                    self.condition(&condition, true);
+                }
+                self.scopes.push(Vec::new());
+                self.children(syntax_node);
+                self.pop_scope();
+            }
+            SyntaxKind::Import => {
+                let import = ast::Import::cast(syntax_node.clone()).unwrap();
+                self.declare(import.name());
+            }
+            SyntaxKind::Catch => {
+                let catch = ast::Catch::cast(syntax_node.clone()).unwrap();
+                self.scopes.push(Vec::new());
+                self.declare(catch.name());
+                self.node(&catch.body());
+                self.pop_scope();
+            }
+            SyntaxKind::BinExpr => {
+                self.nil_comparison(syntax_node);
+                self.children(syntax_node);
+            }
+            _ => self.children(syntax_node),
+        }
+    }
+
+    fn children(&mut self, syntax_node: &SyntaxNode) {
+        for child in syntax_node.children().filter_map(SyntaxElement::into_node) {
+            self.node(&child);
+        }
+    }
+
+    fn declare(&mut self, ident: SyntaxToken) {
+        let name = ident.symbol();
+        let (_, outer) = self.scopes.split_last().unwrap();
+        let shadowed = outer
+            .iter()
+            .rev()
+            .find_map(|scope| scope.iter().rev().find(|local| local.name == name));
+        if let Some(shadowed) = shadowed {
+            let label = (
+                shadowed.declaration.span(),
+                "shadowed variable declared here",
+            );
+            let message = format!(
+                "Variable '{}' shadows a variable from an outer scope.",
+                name
+            );
+            self.report("shadowing", message, ident.span(), Some(label));
+        }
+        self.scopes.last_mut().unwrap().push(Local {
+            name,
+            declaration: ident,
+            reads: 0,
+            writes: 0,
+        });
+    }
+
+    fn lookup(&mut self, name: &Symbol) -> Option<&mut Local> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.iter_mut().rev().find(|local| &local.name == name))
+    }
+
+    fn pop_scope(&mut self) {
+        for local in self.scopes.pop().unwrap() {
+            if local.reads > 0 || local.name.as_str().starts_with('_') {
+                continue;
+            }
+            let span = local.declaration.span();
+            if local.writes == 0 {
+                let message = format!("Unused variable '{}'.", local.name);
+                self.report("unused-variable", message, span, None);
+            } else {
+                let message = format!("Variable '{}' is assigned but never read.", local.name);
+                self.report("unused-assignment", message, span, None);
+            }
+        }
+    }
+
+    fn assign(&mut self, syntax_node: &SyntaxNode) {
+        let assign = ast::Assign::cast(syntax_node.clone()).unwrap();
+        let value = assign.value();
+        // Assigning to an element reads the list rather than writing a variable.
+        let name = match assign.var_name() {
+            Some(name) => name,
+            None => return self.children(syntax_node),
+        };
+        if ast::Identifier::cast(value.clone()).is_some_and(|ident| ident.name() == name) {
+            let message = format!("Variable '{}' is assigned to itself.", name);
+            let span = syntax_node.span().unwrap_or(0..0);
+            self.report("self-assignment", message, span, None);
+        }
+        self.node(&value);
+        if let Some(local) = self.lookup(&name) {
+            local.writes += 1;
+        }
+    }
+
+    /// `while (true)` is the usual way to write an endless loop, so it is
+    /// allowed where `is_loop` is set.
+    fn condition(&mut self, condition: &SyntaxNode, is_loop: bool) {
+        let truthy = match Self::constant(condition) {
+            Some(truthy) => truthy,
+            None => return,
+        };
+        let literal_true = condition
+            .first_token()
+            .is_some_and(|token| token.kind() == SyntaxKind::True)
+            && condition.kind() == SyntaxKind::Literal;
+        if is_loop && literal_true {
+            return;
+        }
+        let message = format!("This condition is always {}.", truthy);
+        let span = condition.span().unwrap_or(0..0);
+        self.report("constant-condition", message, span, None);
+    }
+
+    fn nil_comparison(&mut self, syntax_node: &SyntaxNode) {
+        let bin_expr = ast::BinExpr::cast(syntax_node.clone()).unwrap();
+        let op = bin_expr.op();
+        let always = match op.kind() {
+            SyntaxKind::EqualEqual => false,
+            SyntaxKind::BangEqual => true,
+            _ => return,
+        };
+        let other = match (
+            Self::static_type(&bin_expr.left()),
+            Self::static_type(&bin_expr.right()),
+        ) {
+            (Some("nil"), Some(other)) | (Some(other), Some("nil")) if other != "nil" => other,
+            _ => return,
+        };
+        let message = format!(
+            "Comparing a {} with nil with '{}' is always {}.",
+            other, op, always
+        );
+        let span = syntax_node.span().unwrap_or(0..0);
+        self.report("nil-comparison", message, span, None);
+    }
+
+    /// The truthiness of an expression that folds to a constant.
+    fn constant(syntax_node: &SyntaxNode) -> Option<bool> {
+        let folded = Optimizer::default().optimize(syntax_node.clone());
+        let literal = ast::Literal::cast(folded)?;
+        Some(Interpreter::is_truthy(&Interpreter::literal(
+            &literal.token(),
+        )))
+    }
+
+    /// The type an expression evaluates to, where that is obvious.
+    fn static_type(syntax_node: &SyntaxNode) -> Option<&'static str> {
+        let token = syntax_node.children().find_map(SyntaxElement::into_token)?;
+        match (syntax_node.kind(), token.kind()) {
+            (SyntaxKind::Literal, SyntaxKind::Nil) => Some("nil"),
+            (SyntaxKind::Literal, SyntaxKind::Number) => Some("number"),
+            (SyntaxKind::Literal, SyntaxKind::String) => Some("string"),
+            (SyntaxKind::Interpolation, _) => Some("string"),
+            (SyntaxKind::Literal, _) => Some("boolean"),
+            (SyntaxKind::UnaryExpr, SyntaxKind::Minus) => Some("number"),
+            (SyntaxKind::UnaryExpr, SyntaxKind::Bang) => Some("boolean"),
+            (SyntaxKind::BinExpr, SyntaxKind::Plus) => None,
+            (SyntaxKind::BinExpr, SyntaxKind::Minus)
+            | (SyntaxKind::BinExpr, SyntaxKind::Star)
+            | (SyntaxKind::BinExpr, SyntaxKind::Slash) => Some("number"),
+            (SyntaxKind::BinExpr, _) => Some("boolean"),
+            _ => None,
+        }
+    }
+
+    /// Whether control can never reach the statement after this one.
+    fn never_completes(syntax_node: &SyntaxNode) -> bool {
+        match syntax_node.kind() {
+            SyntaxKind::Break | SyntaxKind::Continue | SyntaxKind::Throw => true,
+            // Errors in the other clauses may be caught, but the `finally`
+            // block always runs.
+            SyntaxKind::Try => ast::Try::cast(syntax_node.clone())
+                .unwrap()
+                .finally_block()
+                .is_some_and(|block| Self::never_completes(&block)),
+            SyntaxKind::While => {
+                let while_condition = ast::While::cast(syntax_node.clone()).unwrap();
+                Self::constant(&while_condition.condition()) == Some(true)
+                    && !Self::breaks(&while_condition.body())
+            }
+            SyntaxKind::For => {
+                let for_loop = ast::For::cast(syntax_node.clone()).unwrap();
+                let forever = match for_loop.condition() {
+                    Some(condition) => Self::constant(&condition) == Some(true),
+                    None => true,
+                };
+                forever && !Self::breaks(&for_loop.body())
+            }
+            SyntaxKind::Block => ast::Block::cast(syntax_node.clone())
+                .unwrap()
+                .children()
+                .any(|child| Self::never_completes(&child)),
+            SyntaxKind::If => {
+                let if_condition = ast::If::cast(syntax_node.clone()).unwrap();
+                Self::never_completes(&if_condition.then_branch())
+                    && if_condition
+                        .else_branch()
+                        .is_some_and(|branch| Self::never_completes(&branch))
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a loop body contains a `break` out of that loop, rather than
+    /// out of a loop nested in it.
+    fn breaks(syntax_node: &SyntaxNode) -> bool {
+        match syntax_node.kind() {
+            SyntaxKind::Break => true,
+            SyntaxKind::While | SyntaxKind::For => false,
+            _ => syntax_node
+                .children()
+                .filter_map(SyntaxElement::into_node)
+                .any(|child| Self::breaks(&child)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LintConfig, Linter};
+    use crate::diagnostic::Severity;
+
+    /// Formats each finding as `rule offset message`.
+    fn lint(config: LintConfig, source: &str) -> Vec<String> {
+        let diagnostics = Linter::new(config).lint(source).unwrap();
+        diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let start = diagnostic.primary().unwrap().span.start;
+                format!(
+                    "{} {} {}",
+                    diagnostic.code().unwrap(),
+                    start,
+                    diagnostic.message()
+                )
+            })
+            .collect()
+    }
+
+    fn check_lint(source: &str, expected: &[&str]) {
+        assert_eq!(lint(LintConfig::default(), source), expected);
+    }
+
+    #[test]
+    fn variables() {
+        check_lint("var a = 1; { var b = 2; print b; }", &[]);
+        check_lint(
+            "{ var a; var b; b = 1; var _c; }",
+            &[
+                "unused-variable 6 Unused variable 'a'.",
+                "unused-assignment 13 Variable 'b' is assigned but never read.",
+            ],
+        );
+        check_lint(
+            "var a = 1; { var a = 2; print a; { var a = a; print a; } }",
+            &[
+                "shadowing 17 Variable 'a' shadows a variable from an outer scope.",
+                "shadowing 39 Variable 'a' shadows a variable from an outer scope.",
+            ],
+        );
+        check_lint(
+            "var a = 1; a = a;",
+            &["self-assignment 11 Variable 'a' is assigned to itself."],
+        );
+    }
+
+    #[test]
+    fn control_flow() {
+        check_lint(
+            "if (1 > 2) print 1; while (!nil) print 2; while (true) print 3;",
+            &[
+                "constant-condition 4 This condition is always false.",
+                "constant-condition 27 This condition is always true.",
+                "unreachable-code 49 Unreachable statement.",
+            ],
+        );
+        check_lint(
+            "var a = 1; { while (true) a = a + 1; print a; print a; }",
+            &["unreachable-code 37 Unreachable statement."],
+        );
+        check_lint("var a = 1; while (a < 10) a = a + 1; print a;", &[]);
+        check_lint(
+            "var a = 1; while (true) { if (a > 1) break; continue; print a; } \
+             for (;;) { while (true) break; } print a;",
+            &[
+                "unreachable-code 54 Unreachable statement.",
+                "unreachable-code 98 Unreachable statement.",
+            ],
+        );
+        check_lint(
+            "try { throw 1; print 1; } catch (_e) {} finally { throw 2; } print 2;",
+            &[
+                "unreachable-code 15 Unreachable statement.",
+                "unreachable-code 61 Unreachable statement.",
+            ],
+        );
+    }
+
+    #[test]
+    fn nil_comparisons() {
+        check_lint(
+            "var a; print nil == 1; print -a != nil; print a == nil; print a + 1 == nil;",
+            &[
+                "nil-comparison 13 Comparing a number with nil with '==' is always false.",
+                "nil-comparison 29 Comparing a number with nil with '!=' is always true.",
+            ],
+        );
+    }
+
+    #[test]
+    fn config() {
+        let config = LintConfig::parse(
+            "# only care about shadowing\nunused-variable = off\nshadowing = error\n",
+        )
+        .unwrap();
+        assert_eq!(config.severity("unused-variable"), None);
+        assert_eq!(config.severity("shadowing"), Some(Severity::Error));
+        assert_eq!(config.severity("self-assignment"), Some(Severity::Warning));
+        assert_eq!(
+            lint(config, "var a; { var a; }"),
+            &["shadowing 13 Variable 'a' shadows a variable from an outer scope."]
+        );
+
+        assert_eq!(
+            LintConfig::parse("shadowing = loud").unwrap_err(),
+            "line 1: unknown level `loud`"
+        );
+        assert_eq!(
+            LintConfig::parse("\n\nmissing = off").unwrap_err(),
+            "line 3: unknown rule `missing`"
+        );
+    }
+}
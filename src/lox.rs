@@ -0,0 +1,279 @@
+use crate::convert::{FromLox, IntoLox};
+use crate::error::{LoxError, RuntimeError, SyntaxError};
+use crate::green::SyntaxNode;
+use crate::interpreter::Interpreter;
+use crate::limits::Limits;
+use crate::optimizer::Optimizer;
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+use crate::value::Value;
+
+/// A Lox session for embedding scripts in a Rust program.
+///
+/// Globals persist between calls, so a host can define values, evaluate
+/// scripts that use them and read back the results.
+#[derive(Default)]
+pub struct Lox {
+    interpreter: Interpreter,
+    optimize: bool,
+}
+
+impl Lox {
+    pub fn new() -> Lox {
+        Lox::default()
+    }
+
+    /// The underlying interpreter, e.g. to define natives or redirect output.
+    pub fn interpreter(&mut self) -> &mut Interpreter {
+        &mut self.interpreter
+    }
+
+    /// Limits applied to each following [`eval`](Lox::eval) separately.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.interpreter.set_limits(limits);
+    }
+
+    /// Whether each following [`eval`](Lox::eval) runs the script through an
+    /// [`Optimizer`] first. Off by default.
+    pub fn set_optimize(&mut self, optimize: bool) {
+        self.optimize = optimize;
+    }
+
+    /// Runs `source` and returns the value of its last statement, which is
+    /// `nil` unless that statement is an expression.
+    pub fn eval(&mut self, source: &str) -> Result<Value, LoxError> {
+        self.interpreter.reset_usage();
+        let mut statements = parse(source).map_err(LoxError::Syntax)?;
+        if self.optimize {
+            let mut optimizer = Optimizer::default();
+            statements = statements
+                .into_iter()
+                .map(|statement| optimizer.optimize(statement))
+                .collect();
+        }
+
+        self.interpreter.set_source(source);
+        let mut value = Value::Nil;
+        for statement in statements {
+            value = self.interpreter.interpret(statement)?;
+        }
+        Ok(value)
+    }
+
+    pub fn set_global(&mut self, name: &str, value: impl IntoLox) {
+        self.interpreter.define_global(name, value.into_lox());
+    }
+
+    pub fn get_global<T: FromLox>(&self, name: &str) -> Result<T, LoxError> {
+        let value = self
+            .interpreter
+            .global(name)
+            .ok_or_else(|| RuntimeError::new(format!("Undefined variable '{}'.", name)))?;
+        Ok(T::from_lox(value)?)
+    }
+
+    /// Calls the global function `name` with already converted arguments.
+    pub fn call_function(&mut self, name: &str, args: Vec<Value>) -> Result<Value, LoxError> {
+        let function = self.get_global::<Value>(name)?;
+        Ok(self.interpreter.call_value(function, &args)?)
+    }
+}
+
+/// Scans, parses and resolves `source`, returning all errors found if it
+/// isn't a valid program.
+pub(crate) fn parse(source: &str) -> Result<Vec<SyntaxNode>, Vec<SyntaxError>> {
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan().cloned().collect();
+    let mut errors = scanner.errors().to_vec();
+    let statements = match Parser::new(tokens).parse() {
+        Ok(statements) => statements,
+        Err(parse_errors) => {
+            errors.extend(parse_errors);
+            Vec::new()
+        }
+    };
+    if errors.is_empty() {
+        if let Err(resolve_errors) = Resolver::new().resolve(&statements) {
+            errors.extend(resolve_errors);
+        }
+    }
+    if errors.is_empty() {
+        Ok(statements)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lox;
+    use crate::{
+        FromLox, Interpreter, IntoLox, Limits, LoxError, MemoryLoader, RuntimeError, Value,
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn eval() {
+        let mut lox = Lox::new();
+        assert_eq!(lox.eval("1 + 2;"), Ok(Value::Number(3.)));
+        assert_eq!(lox.eval("var a = 1;"), Ok(Value::Nil));
+        assert_eq!(lox.eval("a + 1;"), Ok(Value::Number(2.)));
+        assert!(matches!(lox.eval("1 +"), Err(LoxError::Syntax(_))));
+        assert_eq!(
+            lox.eval("b;").map_err(|error| error.to_string()),
+            Err("Undefined variable 'b'.".to_string())
+        );
+    }
+
+    #[test]
+    fn globals() {
+        let mut lox = Lox::new();
+        lox.set_global("name", "world");
+        lox.set_global("count", 2.);
+        lox.set_global("missing", None::<f64>);
+        lox.eval("var greeting = \"hello \" + name; count = count + 1;")
+            .unwrap();
+        assert_eq!(
+            lox.get_global::<String>("greeting"),
+            Ok("hello world".to_string())
+        );
+        assert_eq!(lox.get_global::<f64>("count"), Ok(3.));
+        assert_eq!(lox.get_global::<Option<f64>>("missing"), Ok(None));
+        assert_eq!(lox.get_global::<Option<f64>>("count"), Ok(Some(3.)));
+        assert_eq!(
+            lox.get_global::<bool>("count")
+                .map_err(|error| error.to_string()),
+            Err("Expected a boolean but got number.".to_string())
+        );
+        assert!(lox.get_global::<Value>("undefined").is_err());
+    }
+
+    #[test]
+    fn list_globals() {
+        let mut lox = Lox::new();
+        lox.set_global("xs", vec![1., 2.]);
+        lox.eval("push(xs, xs[0] + xs[1]);").unwrap();
+        assert_eq!(lox.get_global::<Vec<f64>>("xs"), Ok(vec![1., 2., 3.]));
+        lox.eval("push(xs, nil);").unwrap();
+        assert_eq!(
+            lox.get_global::<Vec<f64>>("xs")
+                .map_err(|error| error.to_string()),
+            Err("Expected a number but got nil.".to_string())
+        );
+        assert_eq!(
+            lox.get_global::<Vec<Option<f64>>>("xs"),
+            Ok(vec![Some(1.), Some(2.), Some(3.), None])
+        );
+    }
+
+    #[test]
+    fn map_globals() {
+        let mut lox = Lox::new();
+        let scores = HashMap::from([("b".to_string(), 2.), ("a".to_string(), 1.)]);
+        lox.set_global("scores", scores);
+        lox.eval("scores[\"c\"] = scores[\"a\"] + scores[\"b\"];")
+            .unwrap();
+        assert_eq!(
+            lox.eval("keys(scores);").map(|keys| keys.to_string()),
+            Ok("[a, b, c]".to_string())
+        );
+        let scores = lox.get_global::<HashMap<String, f64>>("scores").unwrap();
+        assert_eq!(scores["c"], 3.);
+        lox.eval("scores[1] = 1;").unwrap();
+        assert_eq!(
+            lox.get_global::<HashMap<String, f64>>("scores")
+                .map_err(|error| error.to_string()),
+            Err("Expected a string but got number.".to_string())
+        );
+    }
+
+    #[test]
+    fn modules() {
+        let mut loader = MemoryLoader::new();
+        loader.insert(
+            "lib/a.lox",
+            "import \"b.lox\" as b; export var a = b.b + len(\"ab\");",
+        );
+        loader.insert("lib/b.lox", "export var b = 1; var secret = 2;");
+        loader.insert("uses_host.lox", "export var x = host;");
+        let mut lox = Lox::new();
+        lox.interpreter().set_loader(loader);
+        lox.interpreter().set_module_path("main.lox");
+        lox.set_global("host", 1.);
+
+        assert_eq!(
+            lox.eval("import \"lib/a.lox\" as a; a.a;"),
+            Ok(Value::Number(3.))
+        );
+        // Each module has its own globals, with only the natives in common.
+        assert_eq!(
+            lox.eval("secret;").map_err(|e| e.to_string()),
+            Err("Undefined variable 'secret'.".to_string())
+        );
+        assert_eq!(
+            lox.eval("import \"uses_host.lox\" as m;")
+                .map_err(|e| e.to_string()),
+            Err("Undefined variable 'host'. [line 1 of 'uses_host.lox']".to_string())
+        );
+        assert_eq!(
+            lox.eval("import \"missing.lox\" as m;")
+                .map_err(|e| e.to_string()),
+            Err("Can't load module 'missing.lox': no such module.".to_string())
+        );
+    }
+
+    #[test]
+    fn optimize() {
+        let mut lox = Lox::new();
+        lox.set_limits(Limits {
+            max_steps: Some(1),
+            ..Limits::default()
+        });
+        assert!(lox.eval("1 + 2 * 3;").is_err());
+        // Folded to a single literal, which takes one step to evaluate.
+        lox.set_optimize(true);
+        assert_eq!(lox.eval("1 + 2 * 3;"), Ok(Value::Number(7.)));
+        assert_eq!(lox.eval("\"a\" + \"b\";"), Ok("ab".into_lox()));
+
+        lox.set_limits(Limits::default());
+        assert_eq!(
+            lox.eval("1 + \"a\";").map_err(|error| error.to_string()),
+            Err("Operands must be two numbers or two strings.".to_string())
+        );
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let mut lox = Lox::new();
+        let lists = |n| format!("len({}{});", "[".repeat(n), "]".repeat(n));
+        // The deepest scripts the parser accepts still run.
+        assert_eq!(lox.eval(&lists(62)), Ok(Value::Number(1.)));
+        assert!(matches!(lox.eval(&lists(63)), Err(LoxError::Syntax(_))));
+        assert!(matches!(
+            lox.eval(&lists(100_000)),
+            Err(LoxError::Syntax(_))
+        ));
+    }
+
+    fn double(_: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+        let n = f64::from_lox(args[0].clone())?;
+        Ok((n * 2.).into_lox())
+    }
+
+    #[test]
+    fn call_function() {
+        let mut lox = Lox::new();
+        lox.interpreter().define_native("double", 1, double);
+        assert_eq!(
+            lox.call_function("double", vec![21.0.into_lox()]),
+            Ok(Value::Number(42.))
+        );
+        assert_eq!(
+            lox.call_function("double", vec![true.into_lox()])
+                .map_err(|error| error.to_string()),
+            Err("Expected a number but got boolean.".to_string())
+        );
+        assert!(lox.call_function("undefined", vec![]).is_err());
+    }
+}
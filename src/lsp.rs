@@ -0,0 +1,893 @@
+//! A Language Server Protocol server for Lox, speaking JSON-RPC with
+//! `Content-Length` framing, as over stdio by the `rlox-lsp` binary.
+
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::error::SyntaxError;
+use crate::green::SyntaxToken;
+use crate::highlight::Highlight;
+use crate::interpreter::Interpreter;
+use crate::json::Json;
+use crate::kinds::SyntaxKind;
+use crate::line_index::LineIndex;
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+use crate::symbols::{DeclarationKind, SymbolTable};
+use crate::value::Value;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+
+const TOKEN_TYPES: &[&str] = &[
+    "keyword", "variable", "string", "number", "operator", "comment",
+];
+const DECLARATION_MODIFIER: usize = 1;
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const PARSE_ERROR: i64 = -32700;
+const INVALID_PARAMS: i64 = -32602;
+
+/// The largest message body the server reads, so a bad `Content-Length`
+/// can't make it allocate without bound.
+const MAX_MESSAGE_LENGTH: usize = 64 << 20;
+
+#[derive(Default)]
+pub struct LanguageServer {
+    documents: HashMap<String, Document>,
+    exited: bool,
+}
+
+/// An open file and everything known about it.
+struct Document {
+    source: String,
+    lines: LineIndex,
+    tokens: Vec<SyntaxToken>,
+    comments: Vec<SyntaxToken>,
+    errors: Vec<SyntaxError>,
+    symbols: SymbolTable,
+}
+
+impl Document {
+    fn new(source: String) -> Document {
+        let mut scanner = Scanner::new(&source);
+        let tokens = scanner.scan().cloned().collect::<Vec<_>>();
+        let mut errors = scanner.errors().to_vec();
+        let mut parser = Parser::new(tokens.clone()).with_docs(scanner.comments());
+        let statements = match parser.parse() {
+            Ok(statements) => statements,
+            Err(parse_errors) => {
+                errors.extend(parse_errors);
+                Vec::new()
+            }
+        };
+        if let Err(resolve_errors) = Resolver::new().resolve(&statements) {
+            errors.extend(resolve_errors);
+        }
+        Document {
+            lines: LineIndex::new(&source),
+            tokens,
+            comments: scanner.comments().to_vec(),
+            errors,
+            symbols: SymbolTable::new(&statements),
+            source,
+        }
+    }
+
+    /// An LSP position: a 0-based line and a column in UTF-16 code units.
+    fn position(&self, offset: usize) -> Json {
+        let offset = offset.min(self.source.len());
+        let line = self.lines.line(offset);
+        let start = self.lines.line_start(line);
+        let character = self.source[start..offset]
+            .chars()
+            .map(char::len_utf16)
+            .sum::<usize>();
+        Json::object([("line", (line - 1).into()), ("character", character.into())])
+    }
+
+    fn range(&self, span: &Range<usize>) -> Json {
+        Json::object([
+            ("start", self.position(span.start)),
+            ("end", self.position(span.end)),
+        ])
+    }
+
+    fn offset(&self, position: &Json) -> Option<usize> {
+        let line = position.get("line")?.as_f64()? as usize + 1;
+        let character = position.get("character")?.as_f64()? as usize;
+        if line > self.lines.line_count() {
+            return None;
+        }
+        let start = self.lines.line_start(line);
+        let mut units = 0;
+        for (index, c) in self.source[start..].char_indices() {
+            if units >= character || c == '\n' {
+                return Some(start + index);
+            }
+            units += c.len_utf16();
+        }
+        Some(self.source.len())
+    }
+}
+
+impl LanguageServer {
+    pub fn new() -> LanguageServer {
+        LanguageServer::default()
+    }
+
+    /// Serves framed messages from `input` until it ends or the client sends
+    /// `exit`.
+    pub fn run(&mut self, mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        while let Some(message) = read_message(&mut input)? {
+            let replies = match Json::parse(&message) {
+                Ok(message) => self.handle(&message),
+                Err(err) => vec![error_response(Json::Null, PARSE_ERROR, err)],
+            };
+            for reply in replies {
+                let body = reply.to_string();
+                write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+            }
+            output.flush()?;
+            if self.exited {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles one request or notification, returning the messages to send
+    /// back.
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let params = message.get("params").cloned().unwrap_or(Json::Null);
+        let result = match method {
+            "initialize" => Some(capabilities()),
+            "shutdown" => Some(Json::Null),
+            "exit" => {
+                self.exited = true;
+                return Vec::new();
+            }
+            "textDocument/didOpen" => {
+                let text = params
+                    .pointer(&["textDocument", "text"])
+                    .and_then(Json::as_str);
+                return self.update(&params, text.map(str::to_string));
+            }
+            "textDocument/didChange" => {
+                let changes = params.get("contentChanges").and_then(Json::as_array);
+                let text = changes
+                    .and_then(<[Json]>::last)
+                    .and_then(|change| change.get("text")?.as_str());
+                return self.update(&params, text.map(str::to_string));
+            }
+            "textDocument/didClose" => return self.update(&params, None),
+            "textDocument/hover" => self.with_position(&params, Self::hover),
+            "textDocument/definition" => self.with_position(&params, Self::definition),
+            "textDocument/references" => self.with_position(&params, Self::references),
+            "textDocument/documentSymbol" => self
+                .document(&params)
+                .map(|(uri, document)| document_symbols(uri, document)),
+            "textDocument/semanticTokens/full" => self
+                .document(&params)
+                .map(|(_, document)| semantic_tokens(document)),
+            _ => match id {
+                Some(id) => {
+                    let message = format!("Unknown method '{}'.", method);
+                    return vec![error_response(id, METHOD_NOT_FOUND, message)];
+                }
+                None => return Vec::new(),
+            },
+        };
+        let id = match id {
+            Some(id) => id,
+            None => return Vec::new(),
+        };
+        match result {
+            Some(result) => vec![Json::object([
+                ("jsonrpc", "2.0".into()),
+                ("id", id),
+                ("result", result),
+            ])],
+            None => vec![error_response(
+                id,
+                INVALID_PARAMS,
+                "Unknown document or position.".to_string(),
+            )],
+        }
+    }
+
+    /// Opens, replaces or, without `text`, closes a document, and publishes
+    /// its diagnostics.
+    fn update(&mut self, params: &Json, text: Option<String>) -> Vec<Json> {
+        let uri = match params
+            .pointer(&["textDocument", "uri"])
+            .and_then(Json::as_str)
+        {
+            Some(uri) => uri.to_string(),
+            None => return Vec::new(),
+        };
+        let diagnostics = match text {
+            Some(text) => {
+                let document = Document::new(text);
+                let diagnostics = publish_diagnostics(&uri, &document);
+                self.documents.insert(uri.clone(), document);
+                diagnostics
+            }
+            None => {
+                self.documents.remove(&uri);
+                Json::Array(Vec::new())
+            }
+        };
+        vec![Json::object([
+            ("jsonrpc", "2.0".into()),
+            ("method", "textDocument/publishDiagnostics".into()),
+            (
+                "params",
+                Json::object([("uri", uri.into()), ("diagnostics", diagnostics)]),
+            ),
+        ])]
+    }
+
+    fn document<'a>(&'a self, params: &'a Json) -> Option<(&'a str, &'a Document)> {
+        let uri = params.pointer(&["textDocument", "uri"])?.as_str()?;
+        Some((uri, self.documents.get(uri)?))
+    }
+
+    fn with_position(
+        &self,
+        params: &Json,
+        handler: fn(&str, &Document, usize, &Json) -> Json,
+    ) -> Option<Json> {
+        let (uri, document) = self.document(params)?;
+        let offset = document.offset(params.get("position")?)?;
+        Some(handler(uri, document, offset, params))
+    }
+
+    fn hover(_: &str, document: &Document, offset: usize, _: &Json) -> Json {
+        let symbols = &document.symbols;
+        let (text, span) = match symbols.declaration_at(offset) {
+            Some(declaration) => {
+                let declaration = &symbols.declarations()[declaration];
+                let source = &document.source[declaration.span.clone()];
+                let signature = match declaration.kind {
+                    DeclarationKind::Var => format!("var {}", source),
+                    DeclarationKind::Import => source.to_string(),
+                    DeclarationKind::Catch => format!("catch ({})", source),
+                };
+                let mut text = format!("```lox\n{}\n```", signature);
+                if let Some(doc) = &declaration.doc {
+                    text.push_str("\n\n");
+                    text.push_str(doc);
+                }
+                (text, declaration.name_span.clone())
+            }
+            None => {
+                let reference = symbols
+                    .references()
+                    .iter()
+                    .find(|reference| reference.span.contains(&offset));
+                let reference = match reference {
+                    Some(reference) => reference,
+                    None => return Json::Null,
+                };
+                let name = &document.source[reference.span.clone()];
+                match Interpreter::default().global(name) {
+                    Some(Value::NativeFunction(function)) => (
+                        format!(
+                            "```lox\nnative fn {}({} arguments)\n```",
+                            name,
+                            function.arity()
+                        ),
+                        reference.span.clone(),
+                    ),
+                    _ => return Json::Null,
+                }
+            }
+        };
+        Json::object([
+            (
+                "contents",
+                Json::object([("kind", "markdown".into()), ("value", text.into())]),
+            ),
+            ("range", document.range(&span)),
+        ])
+    }
+
+    fn definition(uri: &str, document: &Document, offset: usize, _: &Json) -> Json {
+        let symbols = &document.symbols;
+        match symbols.declaration_at(offset) {
+            Some(declaration) => {
+                let declaration = &symbols.declarations()[declaration];
+                location(uri, document, &declaration.name_span)
+            }
+            None => Json::Null,
+        }
+    }
+
+    fn references(uri: &str, document: &Document, offset: usize, params: &Json) -> Json {
+        let symbols = &document.symbols;
+        let declaration = match symbols.declaration_at(offset) {
+            Some(declaration) => declaration,
+            None => return Json::Array(Vec::new()),
+        };
+        let include_declaration = params
+            .pointer(&["context", "includeDeclaration"])
+            .and_then(Json::as_bool)
+            .unwrap_or(false);
+        let mut spans = Vec::new();
+        if include_declaration {
+            spans.push(symbols.declarations()[declaration].name_span.clone());
+        }
+        spans.extend(
+            symbols
+                .references_to(declaration)
+                .map(|reference| reference.span.clone()),
+        );
+        spans.sort_by_key(|span| span.start);
+        Json::Array(
+            spans
+                .iter()
+                .map(|span| location(uri, document, span))
+                .collect(),
+        )
+    }
+}
+
+fn capabilities() -> Json {
+    let legend = Json::object([
+        (
+            "tokenTypes",
+            Json::Array(TOKEN_TYPES.iter().map(|&name| name.into()).collect()),
+        ),
+        ("tokenModifiers", Json::Array(vec!["declaration".into()])),
+    ]);
+    Json::object([
+        (
+            "capabilities",
+            Json::object([
+                // Clients send the whole document on every change.
+                ("textDocumentSync", 1.into()),
+                ("hoverProvider", true.into()),
+                ("definitionProvider", true.into()),
+                ("referencesProvider", true.into()),
+                ("documentSymbolProvider", true.into()),
+                (
+                    "semanticTokensProvider",
+                    Json::object([("legend", legend), ("full", true.into())]),
+                ),
+            ]),
+        ),
+        ("serverInfo", Json::object([("name", "rlox-lsp".into())])),
+    ])
+}
+
+fn location(uri: &str, document: &Document, span: &Range<usize>) -> Json {
+    Json::object([("uri", uri.into()), ("range", document.range(span))])
+}
+
+fn error_response(id: Json, code: i64, message: String) -> Json {
+    Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("id", id),
+        (
+            "error",
+            Json::object([
+                ("code", Json::Number(code as f64)),
+                ("message", message.into()),
+            ]),
+        ),
+    ])
+}
+
+fn publish_diagnostics(uri: &str, document: &Document) -> Json {
+    let diagnostics = document.errors.iter().map(|error| {
+        let diagnostic = Diagnostic::from(error);
+        let severity = match diagnostic.severity() {
+            Severity::Error => 1,
+            Severity::Warning => 2,
+        };
+        let related = diagnostic
+            .labels()
+            .iter()
+            .map(|label| {
+                Json::object([
+                    ("location", location(uri, document, &label.span)),
+                    ("message", label.message.as_str().into()),
+                ])
+            })
+            .collect::<Vec<_>>();
+        Json::object([
+            ("range", document.range(&error.span())),
+            ("severity", severity.into()),
+            ("source", "rlox".into()),
+            ("message", error.message().into()),
+            ("relatedInformation", Json::Array(related)),
+        ])
+    });
+    Json::Array(diagnostics.collect())
+}
+
+fn document_symbols(uri: &str, document: &Document) -> Json {
+    const VARIABLE: usize = 13;
+    let symbols = document.symbols.declarations().iter().map(|declaration| {
+        Json::object([
+            ("name", declaration.name.as_str().into()),
+            ("kind", VARIABLE.into()),
+            ("location", location(uri, document, &declaration.span)),
+        ])
+    });
+    Json::Array(symbols.collect())
+}
+
+fn token_type(kind: SyntaxKind) -> Option<usize> {
+    let name = match Highlight::of(kind) {
+        Highlight::Keyword => "keyword",
+        Highlight::Identifier => "variable",
+        Highlight::String => "string",
+        Highlight::Number => "number",
+        Highlight::Operator => "operator",
+        Highlight::Comment => "comment",
+        Highlight::Punctuation => return None,
+    };
+    TOKEN_TYPES.iter().position(|&known| known == name)
+}
+
+/// Tokens in the relative encoding of the protocol: each is the line delta,
+/// start delta, length, type and modifiers, split at line breaks.
+fn semantic_tokens(document: &Document) -> Json {
+    let mut tokens = document
+        .tokens
+        .iter()
+        .chain(&document.comments)
+        .collect::<Vec<_>>();
+    tokens.sort_by_key(|token| token.offset());
+    let declarations = document
+        .symbols
+        .declarations()
+        .iter()
+        .map(|declaration| declaration.name_span.start)
+        .collect::<Vec<_>>();
+
+    let mut data = Vec::new();
+    let (mut previous_line, mut previous_start) = (0, 0);
+    for token in tokens {
+        let token_type = match token_type(token.kind()) {
+            Some(token_type) => token_type,
+            None => continue,
+        };
+        let modifiers = if declarations.contains(&token.offset()) {
+            DECLARATION_MODIFIER
+        } else {
+            0
+        };
+        let mut offset = token.offset();
+        for part in token.text().split('\n') {
+            let position = document.position(offset);
+            let line = position.get("line").and_then(Json::as_f64).unwrap() as usize;
+            let start = position.get("character").and_then(Json::as_f64).unwrap() as usize;
+            let delta_start = if line == previous_line {
+                start - previous_start
+            } else {
+                start
+            };
+            let length = part.chars().map(char::len_utf16).sum::<usize>();
+            if length > 0 {
+                for value in [
+                    line - previous_line,
+                    delta_start,
+                    length,
+                    token_type,
+                    modifiers,
+                ] {
+                    data.push(value.into());
+                }
+                previous_line = line;
+                previous_start = start;
+            }
+            offset += part.len() + 1;
+        }
+    }
+    Json::object([("data", Json::Array(data))])
+}
+
+/// Reads one message body, or `None` at the end of the input.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+    if length > MAX_MESSAGE_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {} bytes is too long", length),
+        ));
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LanguageServer;
+    use crate::json::Json;
+    use std::io::{BufRead, Cursor, Read};
+
+    /// Scripts a session: messages are queued, then served in one run.
+    #[derive(Default)]
+    struct Client {
+        input: Vec<u8>,
+        next_id: usize,
+    }
+
+    impl Client {
+        fn send(&mut self, message: Json) {
+            let body = message.to_string();
+            let header = format!("Content-Length: {}\r\n\r\n", body.len());
+            self.input.extend(header.bytes().chain(body.bytes()));
+        }
+
+        fn request(&mut self, method: &str, params: Json) -> usize {
+            self.next_id += 1;
+            self.send(Json::object([
+                ("jsonrpc", "2.0".into()),
+                ("id", self.next_id.into()),
+                ("method", method.into()),
+                ("params", params),
+            ]));
+            self.next_id
+        }
+
+        fn notify(&mut self, method: &str, params: Json) {
+            self.send(Json::object([
+                ("jsonrpc", "2.0".into()),
+                ("method", method.into()),
+                ("params", params),
+            ]));
+        }
+
+        fn at(&mut self, method: &str, line: usize, character: usize) -> usize {
+            self.request(
+                method,
+                Json::object([
+                    ("textDocument", Json::object([("uri", URI.into())])),
+                    (
+                        "position",
+                        Json::object([("line", line.into()), ("character", character.into())]),
+                    ),
+                    (
+                        "context",
+                        Json::object([("includeDeclaration", true.into())]),
+                    ),
+                ]),
+            )
+        }
+
+        fn run(self) -> Session {
+            let mut output = Vec::new();
+            LanguageServer::new()
+                .run(Cursor::new(self.input), &mut output)
+                .unwrap();
+            let mut output = Cursor::new(output);
+            let mut messages = Vec::new();
+            loop {
+                let mut header = String::new();
+                if output.read_line(&mut header).unwrap() == 0 {
+                    break;
+                }
+                let length = header["Content-Length: ".len()..].trim().parse().unwrap();
+                output.read_line(&mut String::new()).unwrap();
+                let mut body = vec![0; length];
+                output.read_exact(&mut body).unwrap();
+                messages.push(Json::parse(std::str::from_utf8(&body).unwrap()).unwrap());
+            }
+            Session { messages }
+        }
+    }
+
+    struct Session {
+        messages: Vec<Json>,
+    }
+
+    impl Session {
+        fn result(&self, id: usize) -> &Json {
+            let id = Json::from(id);
+            let response = self
+                .messages
+                .iter()
+                .find(|message| message.get("id") == Some(&id));
+            response
+                .and_then(|response| response.get("result"))
+                .unwrap()
+        }
+
+        fn notifications(&self, method: &str) -> Vec<&Json> {
+            let method = Json::from(method);
+            self.messages
+                .iter()
+                .filter(|message| message.get("method") == Some(&method))
+                .collect()
+        }
+    }
+
+    const URI: &str = "file:///test.lox";
+
+    fn open(client: &mut Client, text: &str) {
+        client.notify(
+            "textDocument/didOpen",
+            Json::object([(
+                "textDocument",
+                Json::object([
+                    ("uri", URI.into()),
+                    ("languageId", "lox".into()),
+                    ("version", 1.into()),
+                    ("text", text.into()),
+                ]),
+            )]),
+        );
+    }
+
+    /// `(line, character)` pairs of the locations in a result.
+    fn positions(locations: &Json) -> Vec<(f64, f64)> {
+        locations
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|location| {
+                let start = location.pointer(&["range", "start"]).unwrap();
+                (
+                    start.get("line").unwrap().as_f64().unwrap(),
+                    start.get("character").unwrap().as_f64().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    const SOURCE: &str =
+        "var count = 0; // tally\n{\n  var step = 2;\n  count = count + step;\n}\nprint clock();\n";
+
+    #[test]
+    fn session() {
+        let mut client = Client::default();
+        let initialize = client.request("initialize", Json::object([]));
+        client.notify("initialized", Json::object([]));
+        open(&mut client, SOURCE);
+        let hover = client.at("textDocument/hover", 3, 11);
+        let native = client.at("textDocument/hover", 5, 7);
+        let definition = client.at("textDocument/definition", 3, 3);
+        let references = client.at("textDocument/references", 0, 5);
+        let symbols = client.request(
+            "textDocument/documentSymbol",
+            Json::object([("textDocument", Json::object([("uri", URI.into())]))]),
+        );
+        let tokens = client.request(
+            "textDocument/semanticTokens/full",
+            Json::object([("textDocument", Json::object([("uri", URI.into())]))]),
+        );
+        let unknown = client.request("workspace/unknown", Json::Null);
+        let shutdown = client.request("shutdown", Json::Null);
+        client.notify("exit", Json::Null);
+        // Never read: the server stops at `exit`.
+        client.request("shutdown", Json::Null);
+        let session = client.run();
+
+        let capabilities = session.result(initialize).get("capabilities").unwrap();
+        assert_eq!(capabilities.get("hoverProvider"), Some(&Json::Bool(true)));
+
+        let diagnostics = session.notifications("textDocument/publishDiagnostics");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].pointer(&["params", "diagnostics"]),
+            Some(&Json::Array(Vec::new()))
+        );
+
+        let hover = session.result(hover).pointer(&["contents", "value"]);
+        assert_eq!(hover.unwrap().as_str(), Some("```lox\nvar count = 0\n```"));
+        let native = session.result(native).pointer(&["contents", "value"]);
+        assert_eq!(
+            native.unwrap().as_str(),
+            Some("```lox\nnative fn clock(0 arguments)\n```")
+        );
+
+        let definition = session.result(definition);
+        assert_eq!(
+            positions(&Json::Array(vec![definition.clone()])),
+            vec![(0., 4.)]
+        );
+        assert_eq!(
+            positions(session.result(references)),
+            vec![(0., 4.), (3., 2.), (3., 10.)]
+        );
+
+        let symbols = session.result(symbols).as_array().unwrap();
+        let names = symbols
+            .iter()
+            .map(|symbol| symbol.get("name").unwrap().as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["count", "step"]);
+
+        let data = session
+            .result(tokens)
+            .get("data")
+            .unwrap()
+            .as_array()
+            .unwrap();
+        let data = data
+            .iter()
+            .map(|value| value.as_f64().unwrap() as usize)
+            .collect::<Vec<_>>();
+        // `var`, then `count` declared on the same line four columns on.
+        assert_eq!(&data[..10], &[0, 0, 3, 0, 0, 0, 4, 5, 1, 1]);
+        // The comment, then `var` on line 2.
+        assert_eq!(&data[20..30], &[0, 3, 8, 5, 0, 2, 2, 3, 0, 0]);
+
+        let unknown = session
+            .messages
+            .iter()
+            .find(|message| message.get("id") == Some(&Json::from(unknown)));
+        assert_eq!(
+            unknown.unwrap().pointer(&["error", "code"]),
+            Some(&Json::Number(-32601.))
+        );
+        assert_eq!(session.result(shutdown), &Json::Null);
+        assert!(session
+            .messages
+            .iter()
+            .all(|message| message.get("id") != Some(&Json::from(shutdown + 1))));
+    }
+
+    #[test]
+    fn too_long_message() {
+        let input = "Content-Length: 99999999999\r\n\r\n{}";
+        let error = LanguageServer::new()
+            .run(Cursor::new(input), Vec::new())
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn unsupported_keywords() {
+        let mut client = Client::default();
+        open(&mut client, "fun");
+        client.notify(
+            "textDocument/didChange",
+            Json::object([
+                ("textDocument", Json::object([("uri", URI.into())])),
+                (
+                    "contentChanges",
+                    Json::Array(vec![Json::object([("text", "fun;\nreturn;".into())])]),
+                ),
+            ]),
+        );
+        let hover = client.at("textDocument/hover", 0, 1);
+        let session = client.run();
+        let published = session.notifications("textDocument/publishDiagnostics");
+        // Each version is answered with its syntax errors.
+        let counts = published
+            .iter()
+            .map(|notification| {
+                let diagnostics = notification.pointer(&["params", "diagnostics"]).unwrap();
+                diagnostics.as_array().unwrap().len()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(counts, vec![1, 2]);
+        assert_eq!(session.result(hover), &Json::Null);
+    }
+
+    #[test]
+    fn hover_doc_comments() {
+        let mut client = Client::default();
+        open(
+            &mut client,
+            "/// How many.\n///\n///  Indented.\nexport var count = 0;\nprint count;\n",
+        );
+        let hover = client.at("textDocument/hover", 4, 7);
+        let session = client.run();
+        let hover = session.result(hover).pointer(&["contents", "value"]);
+        assert_eq!(
+            hover.unwrap().as_str(),
+            Some("```lox\nvar count = 0\n```\n\nHow many.\n\n Indented.")
+        );
+    }
+
+    #[test]
+    fn hover_catch_variable() {
+        let mut client = Client::default();
+        open(
+            &mut client,
+            "try {\n  throw 1;\n} catch (e) {\n  print e;\n}\n",
+        );
+        let declaration = client.at("textDocument/hover", 2, 9);
+        let reference = client.at("textDocument/hover", 3, 8);
+        let session = client.run();
+        for hover in [declaration, reference] {
+            let hover = session.result(hover).pointer(&["contents", "value"]);
+            assert_eq!(hover.unwrap().as_str(), Some("```lox\ncatch (e)\n```"));
+        }
+    }
+
+    #[test]
+    fn hover_import() {
+        let mut client = Client::default();
+        open(&mut client, "import \"x.lox\" as m;\nprint m;\n");
+        let declaration = client.at("textDocument/hover", 0, 19);
+        let reference = client.at("textDocument/hover", 1, 6);
+        let session = client.run();
+        for hover in [declaration, reference] {
+            let hover = session.result(hover).pointer(&["contents", "value"]);
+            assert_eq!(
+                hover.unwrap().as_str(),
+                Some("```lox\nimport \"x.lox\" as m\n```")
+            );
+        }
+    }
+
+    #[test]
+    fn diagnostics() {
+        let mut client = Client::default();
+        open(&mut client, "{\n  var a = a;\n}\nprint 1");
+        client.notify(
+            "textDocument/didChange",
+            Json::object([
+                ("textDocument", Json::object([("uri", URI.into())])),
+                (
+                    "contentChanges",
+                    Json::Array(vec![Json::object([("text", "print 1;".into())])]),
+                ),
+            ]),
+        );
+        let session = client.run();
+        let published = session.notifications("textDocument/publishDiagnostics");
+        let diagnostics = published[0]
+            .pointer(&["params", "diagnostics"])
+            .unwrap()
+            .as_array()
+            .unwrap();
+        let messages = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.get("message").unwrap().as_str().unwrap())
+            .collect::<Vec<_>>();
+        // The resolver only runs on programs that parse.
+        assert_eq!(messages, vec!["Expect ';' after value."]);
+        assert_eq!(
+            diagnostics[0].pointer(&["range", "start", "line"]),
+            Some(&Json::Number(3.))
+        );
+        let fixed = published[1].pointer(&["params", "diagnostics"]);
+        assert_eq!(fixed, Some(&Json::Array(Vec::new())));
+
+        let mut client = Client::default();
+        open(&mut client, "{\n  var a = a;\n}\n");
+        let session = client.run();
+        let published = session.notifications("textDocument/publishDiagnostics");
+        let diagnostic = published[0].pointer(&["params", "diagnostics"]).unwrap();
+        let diagnostic = &diagnostic.as_array().unwrap()[0];
+        let related = diagnostic
+            .get("relatedInformation")
+            .unwrap()
+            .as_array()
+            .unwrap();
+        assert_eq!(
+            related[0].get("message").unwrap().as_str(),
+            Some("variable declared here")
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use rlox::{
+    Diagnostic, DocFormat, DocGenerator, Formatter, Highlighter, LineIndex, LintConfig, Linter,
+    Lox, LoxError, RenameError, Renderer, Severity, TextEdit,
+};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::process;
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.as_slice() {
+        [] => run_prompt(),
+        [command, args @ ..] if command == "fmt" => format_files(args),
+        [command, args @ ..] if command == "lint" => lint_files(args),
+        [command, args @ ..] if command == "highlight" => highlight(args),
+        [command, args @ ..] if command == "doc" => document(args),
+        [command, path, position, new_name] if command == "rename" => {
+            rename(path, position, new_name)
+        }
+        [path] => run_file(path),
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: rlox [script]");
+    eprintln!("       rlox fmt [--check] <file>...");
+    eprintln!("       rlox lint [--config <file>] <file>...");
+    eprintln!("       rlox highlight [--format ansi|html] <file>");
+    eprintln!("       rlox doc [--format html|markdown] <dir> [-o <dir>]");
+    eprintln!("       rlox rename <file> <line>:<column> <new-name>");
+    process::exit(64);
+}
+
+fn read(path: &str) -> String {
+    match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("Could not read {}: {}", path, err);
+            process::exit(74);
+        }
+    }
+}
+
+/// Formats files in place, or with `--check` only reports the ones that
+/// would change and exits with 1 if there are any.
+fn format_files(args: &[String]) {
+    let check = args.iter().any(|arg| arg == "--check");
+    let paths = args
+        .iter()
+        .filter(|arg| *arg != "--check")
+        .collect::<Vec<_>>();
+    if paths.is_empty() {
+        usage();
+    }
+    let formatter = Formatter::new();
+    let mut status = 0;
+    for path in paths {
+        let source = read(path);
+        let formatted = match formatter.format(&source) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                report(&LoxError::Syntax(errors), path, &source);
+                status = 65;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if check {
+            eprintln!("{} is not formatted", path);
+            status = status.max(1);
+        } else if let Err(err) = std::fs::write(path, formatted) {
+            eprintln!("Could not write {}: {}", path, err);
+            process::exit(74);
+        }
+    }
+    process::exit(status);
+}
+
+/// Lints files with the rules configured in `--config`, or in `.rloxlint`
+/// in the working directory if there is one. Exits with 1 if any finding is
+/// an error.
+fn lint_files(args: &[String]) {
+    let (config, paths) = match args {
+        [flag, config, paths @ ..] if flag == "--config" => (Some(read(config)), paths),
+        paths => (std::fs::read_to_string(".rloxlint").ok(), paths),
+    };
+    if paths.is_empty() {
+        usage();
+    }
+    let config = match config.map(|text| LintConfig::parse(&text)) {
+        Some(Ok(config)) => config,
+        Some(Err(err)) => {
+            eprintln!("Invalid lint config: {}", err);
+            process::exit(64);
+        }
+        None => LintConfig::default(),
+    };
+    let linter = Linter::new(config);
+    let renderer = renderer();
+    let mut status = 0;
+    for path in paths {
+        let source = read(path);
+        match linter.lint(&source) {
+            Ok(diagnostics) => {
+                for diagnostic in diagnostics {
+                    if diagnostic.severity() == Severity::Error {
+                        status = status.max(1);
+                    }
+                    eprint!("{}", renderer.render(&diagnostic, path, &source));
+                }
+            }
+            Err(errors) => {
+                report(&LoxError::Syntax(errors), path, &source);
+                status = 65;
+            }
+        }
+    }
+    process::exit(status);
+}
+
+/// Prints a file with syntax highlighting, as ANSI colours by default or
+/// as a standalone HTML page.
+fn highlight(args: &[String]) {
+    let (format, path) = match args {
+        [flag, format, path] | [path, flag, format] if flag == "--format" => {
+            (format.as_str(), path)
+        }
+        [path] => ("ansi", path),
+        _ => usage(),
+    };
+    let source = read(path);
+    match format {
+        "ansi" => print!("{}", Highlighter::ansi().highlight(&source)),
+        "html" => print!("{}", Highlighter::html_page(path, &source)),
+        _ => usage(),
+    }
+}
+
+/// Writes reference pages for the modules under a directory, from their
+/// doc comments, to `-o` or `doc` in the working directory.
+fn document(args: &[String]) {
+    let mut format = DocFormat::Html;
+    let mut output = "doc";
+    let mut input = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" => output = args.next().unwrap_or_else(|| usage()),
+            "--format" => {
+                format = match args.next().map(String::as_str) {
+                    Some("html") => DocFormat::Html,
+                    Some("markdown") => DocFormat::Markdown,
+                    _ => usage(),
+                }
+            }
+            _ if input.is_none() => input = Some(arg),
+            _ => usage(),
+        }
+    }
+    let input = input.unwrap_or_else(|| usage());
+
+    let mut paths = Vec::new();
+    if let Err(err) = lox_files(Path::new(input), "", &mut paths) {
+        eprintln!("Could not read {}: {}", input, err);
+        process::exit(74);
+    }
+    paths.sort();
+    let mut generator = DocGenerator::new(format);
+    let mut status = 0;
+    for path in &paths {
+        let file = Path::new(input).join(path).display().to_string();
+        let source = read(&file);
+        if let Err(errors) = generator.add(path, &source) {
+            report(&LoxError::Syntax(errors), &file, &source);
+            status = 65;
+        }
+    }
+    if status != 0 {
+        process::exit(status);
+    }
+
+    for (page, contents) in generator.pages() {
+        let page = Path::new(output).join(page);
+        let written = page
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&page, contents));
+        if let Err(err) = written {
+            eprintln!("Could not write {}: {}", page.display(), err);
+            process::exit(74);
+        }
+    }
+}
+
+/// Collects the `.lox` files under `dir`, as paths relative to it with `/`
+/// separators, each starting with `prefix`.
+fn lox_files(dir: &Path, prefix: &str, paths: &mut Vec<String>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = format!("{}{}", prefix, name);
+        if entry.file_type()?.is_dir() {
+            lox_files(&entry.path(), &format!("{}/", path), paths)?;
+        } else if name.ends_with(".lox") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Renames the variable at the 1-based `line:column` position in place.
+fn rename(path: &str, position: &str, new_name: &str) {
+    let source = read(path);
+    let lines = LineIndex::new(&source);
+    let offset = position
+        .split_once(':')
+        .and_then(|(line, column)| Some((line.parse().ok()?, column.parse().ok()?)))
+        .filter(|&(line, column)| line >= 1 && line <= lines.line_count() && column >= 1)
+        .and_then(|(line, column): (usize, usize)| {
+            let start = lines.line_start(line);
+            let mut offsets = source[start..]
+                .char_indices()
+                .map(|(index, _)| start + index);
+            offsets.nth(column - 1)
+        });
+    let offset = match offset {
+        Some(offset) => offset,
+        None => {
+            eprintln!("Invalid position '{}' in {}.", position, path);
+            process::exit(64);
+        }
+    };
+    match rlox::rename(&source, offset, new_name) {
+        Ok(edits) => {
+            if let Err(err) = std::fs::write(path, TextEdit::apply(&source, &edits)) {
+                eprintln!("Could not write {}: {}", path, err);
+                process::exit(74);
+            }
+        }
+        Err(RenameError::Syntax(errors)) => {
+            report(&LoxError::Syntax(errors), path, &source);
+            process::exit(65);
+        }
+        Err(RenameError::Conflict(error)) => {
+            eprint!("{}", renderer().render(&(&error).into(), path, &source));
+            process::exit(1);
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    }
+}
+
+fn run_file(path: &str) {
+    let source = read(path);
+    let mut lox = Lox::new();
+    lox.interpreter().set_module_path(path);
+    if let Err(error) = lox.eval(&source) {
+        report(&error, path, &source);
+        process::exit(match error {
+            LoxError::Syntax(_) => 65,
+            LoxError::Runtime(_) => 70,
+        });
+    }
+}
+
+fn run_prompt() {
+    let mut lox = Lox::new();
+    let stdin = io::stdin();
+    // Piped input isn't shown by the terminal, so show it after the prompt.
+    let echo = !stdin.is_terminal() && io::stdout().is_terminal();
+    loop {
+        print!("> ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        if echo {
+            print!("{}", Highlighter::ansi().highlight(&line));
+        }
+        if let Err(error) = lox.eval(&line) {
+            report(&error, "<stdin>", &line);
+        }
+    }
+}
+
+fn renderer() -> Renderer {
+    if io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none() {
+        Renderer::ansi()
+    } else {
+        Renderer::plain()
+    }
+}
+
+fn report(error: &LoxError, file: &str, source: &str) {
+    let renderer = renderer();
+    match error {
+        LoxError::Syntax(errors) => {
+            for error in errors {
+                eprint!("{}", renderer.render(&error.into(), file, source));
+            }
+        }
+        LoxError::Runtime(error) => {
+            let mut diagnostic = Diagnostic::from(error);
+            if error.trace().len() > 1 {
+                let backtrace = error.backtrace(&LineIndex::new(source));
+                diagnostic = diagnostic.with_note(format!("backtrace:\n{}", backtrace));
+            }
+            eprint!("{}", renderer.render(&diagnostic, file, source));
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::error::RuntimeError;
+use crate::value::Value;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A [`Value`] that can be used as a map key.
+///
+/// Strings, numbers, booleans and `nil` are hashed by value, so keys are
+/// equal exactly when the values are equal with `==`. Numbers are hashed by
+/// their bits, with `-0` folded into `0`. `NaN` is rejected because it is not
+/// equal to itself, and lists, maps, functions and modules because they are compared
+/// by identity and can change while they are in a map.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Nil,
+    Bool(bool),
+    Number(u64),
+    String(Rc<str>),
+}
+
+impl MapKey {
+    pub fn new(value: &Value) -> Result<MapKey, RuntimeError> {
+        match value {
+            Value::Nil => Ok(MapKey::Nil),
+            Value::Bool(b) => Ok(MapKey::Bool(*b)),
+            Value::Number(n) if n.is_nan() => Err(RuntimeError::new("Can't use NaN as a map key.")),
+            Value::Number(n) if *n == 0. => Ok(MapKey::Number(0f64.to_bits())),
+            Value::Number(n) => Ok(MapKey::Number(n.to_bits())),
+            Value::String(s) => Ok(MapKey::String(Rc::clone(s))),
+            Value::NativeFunction(_) | Value::List(_) | Value::Map(_) | Value::Module(_) => Err(
+                RuntimeError::new(format!("Can't use a {} as a map key.", value.type_name())),
+            ),
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            MapKey::Nil => Value::Nil,
+            MapKey::Bool(b) => Value::Bool(*b),
+            MapKey::Number(bits) => Value::Number(f64::from_bits(*bits)),
+            MapKey::String(s) => Value::String(Rc::clone(s)),
+        }
+    }
+}
+
+/// The entries of a map value, kept in the order their keys were first
+/// inserted.
+#[derive(Debug, Clone, Default)]
+pub struct Map {
+    entries: Vec<(MapKey, Value)>,
+    positions: HashMap<MapKey, usize>,
+}
+
+impl Map {
+    pub fn new() -> Map {
+        Map::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &MapKey) -> Option<&Value> {
+        let position = *self.positions.get(key)?;
+        Some(&self.entries[position].1)
+    }
+
+    pub fn contains_key(&self, key: &MapKey) -> bool {
+        self.positions.contains_key(key)
+    }
+
+    /// Sets the value for `key`, which keeps its place if it was already in
+    /// the map, and returns the old value.
+    pub fn insert(&mut self, key: MapKey, value: Value) -> Option<Value> {
+        match self.positions.get(&key) {
+            Some(&position) => Some(std::mem::replace(&mut self.entries[position].1, value)),
+            None => {
+                self.positions.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &MapKey) -> Option<Value> {
+        let position = self.positions.remove(key)?;
+        let (_, value) = self.entries.remove(position);
+        for later in self.positions.values_mut() {
+            if *later > position {
+                *later -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&MapKey, &Value)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Map, MapKey};
+    use crate::Value;
+
+    fn key(value: Value) -> MapKey {
+        MapKey::new(&value).unwrap()
+    }
+
+    #[test]
+    fn keys() {
+        assert_eq!(key(Value::Number(0.)), key(Value::Number(-0.)));
+        assert_ne!(key(Value::Number(1.)), key(Value::String("1".into())));
+        assert_eq!(key(Value::Number(-0.)).to_value().to_string(), "0");
+        let error = MapKey::new(&Value::Number(f64::NAN)).unwrap_err();
+        assert_eq!(error.message(), "Can't use NaN as a map key.");
+        let error = MapKey::new(&Value::list(vec![])).unwrap_err();
+        assert_eq!(error.message(), "Can't use a list as a map key.");
+    }
+
+    #[test]
+    fn insertion_order() {
+        let mut map = Map::new();
+        for name in ["a", "b", "c"] {
+            map.insert(key(Value::String(name.into())), Value::Nil);
+        }
+        map.insert(key(Value::String("a".into())), Value::Bool(true));
+        assert_eq!(
+            map.remove(&key(Value::String("b".into()))),
+            Some(Value::Nil)
+        );
+        map.insert(key(Value::String("b".into())), Value::Nil);
+        let entries = map
+            .iter()
+            .map(|(key, value)| format!("{}={}", key.to_value(), value))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, vec!["a=true", "c=nil", "b=nil"]);
+        assert_eq!(map.get(&key(Value::String("c".into()))), Some(&Value::Nil));
+        assert_eq!(map.len(), 3);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::intern::Symbol;
+use crate::value::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Finds and reads the source of the modules scripts import.
+pub trait ModuleLoader {
+    /// The path of the module that `import path` names in the module at
+    /// `importer`, or in a script without a path if `importer` is `None`.
+    /// Modules with the same resolved path are only loaded once.
+    ///
+    /// By default relative paths are joined to the importer's directory.
+    fn resolve(&self, importer: Option<&str>, path: &str) -> String {
+        let base = importer
+            .and_then(|importer| Path::new(importer).parent())
+            .unwrap_or_else(|| Path::new(""));
+        normalize(&base.join(path))
+    }
+
+    fn load(&self, path: &str) -> io::Result<String>;
+}
+
+/// Loads modules from the file system.
+#[derive(Debug, Default)]
+pub struct FileLoader;
+
+impl ModuleLoader for FileLoader {
+    fn load(&self, path: &str) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+}
+
+/// Serves modules from memory, keyed by their resolved path.
+#[derive(Debug, Default)]
+pub struct MemoryLoader {
+    modules: HashMap<String, String>,
+}
+
+impl MemoryLoader {
+    pub fn new() -> MemoryLoader {
+        MemoryLoader::default()
+    }
+
+    pub fn insert(&mut self, path: &str, source: impl Into<String>) {
+        self.modules
+            .insert(normalize(Path::new(path)), source.into());
+    }
+}
+
+impl ModuleLoader for MemoryLoader {
+    fn load(&self, path: &str) -> io::Result<String> {
+        self.modules
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such module"))
+    }
+}
+
+/// Removes `.` and folds `..` into the component before it, without
+/// touching the file system.
+fn normalize(path: &Path) -> String {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir) => {}
+                _ => normalized.push(".."),
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized.to_string_lossy().into_owned()
+}
+
+/// A loaded module, with the values it exported.
+#[derive(Debug)]
+pub struct Module {
+    path: String,
+    exports: HashMap<Symbol, Value>,
+}
+
+impl Module {
+    pub(crate) fn new(path: String, exports: HashMap<Symbol, Value>) -> Module {
+        Module { path, exports }
+    }
+
+    /// The resolved path the module was loaded from.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.exports.get(&Symbol::intern(name)).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileLoader, MemoryLoader, ModuleLoader};
+
+    #[test]
+    fn resolve() {
+        let loader = FileLoader;
+        assert_eq!(loader.resolve(None, "./lib/a.lox"), "lib/a.lox");
+        assert_eq!(loader.resolve(Some("main.lox"), "a.lox"), "a.lox");
+        assert_eq!(
+            loader.resolve(Some("src/lib/a.lox"), "../b/./c.lox"),
+            "src/b/c.lox"
+        );
+        assert_eq!(loader.resolve(Some("a.lox"), "../../b.lox"), "../../b.lox");
+        assert_eq!(loader.resolve(Some("/src/a.lox"), "../../b.lox"), "/b.lox");
+        assert_eq!(
+            loader.resolve(Some("src/a.lox"), "/lib/b.lox"),
+            "/lib/b.lox"
+        );
+    }
+
+    #[test]
+    fn memory() {
+        let mut loader = MemoryLoader::new();
+        loader.insert("./lib/a.lox", "var a;");
+        assert_eq!(loader.load("lib/a.lox").unwrap(), "var a;");
+        assert!(loader.load("a.lox").is_err());
+    }
+}
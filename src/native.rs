@@ -0,0 +1,165 @@
+use crate::error::RuntimeError;
+use crate::interpreter::Interpreter;
+use crate::map::{Map, MapKey};
+use crate::value::Value;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub type NativeFn = fn(&mut Interpreter, &[Value]) -> Result<Value, RuntimeError>;
+
+/// A function implemented in Rust and exposed to scripts as a global.
+pub struct NativeFunction {
+    name: String,
+    arity: usize,
+    function: NativeFn,
+}
+
+impl NativeFunction {
+    pub fn new(name: &str, arity: usize, function: NativeFn) -> NativeFunction {
+        NativeFunction {
+            name: name.to_string(),
+            arity,
+            function,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: &[Value],
+    ) -> Result<Value, RuntimeError> {
+        (self.function)(interpreter, arguments)
+    }
+}
+
+/// Natives are compared by identity: two globals are equal only if they
+/// refer to the same definition.
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &NativeFunction) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+impl fmt::Display for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt("<native fn>", f)
+    }
+}
+
+/// Registers the builtins every interpreter starts with.
+pub fn define_builtins(interpreter: &mut Interpreter) {
+    interpreter.define_native("clock", 0, clock);
+    interpreter.define_native("len", 1, len);
+    interpreter.define_native("push", 2, push);
+    interpreter.define_native("pop", 1, pop);
+    interpreter.define_native("keys", 1, keys);
+    interpreter.define_native("values", 1, values);
+    interpreter.define_native("has", 2, has);
+    interpreter.define_native("remove", 2, remove);
+}
+
+/// Seconds since the Unix epoch.
+fn clock(_: &mut Interpreter, _: &[Value]) -> Result<Value, RuntimeError> {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|err| RuntimeError::new(err.to_string()))?;
+    Ok(Value::Number(elapsed.as_secs_f64()))
+}
+
+/// The number of elements in a list or map, or characters in a string.
+fn len(_: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    match &args[0] {
+        Value::List(list) => Ok(Value::Number(list.borrow().len() as f64)),
+        Value::Map(map) => Ok(Value::Number(map.borrow().len() as f64)),
+        Value::String(s) => Ok(Value::Number(s.chars().count() as f64)),
+        value => Err(RuntimeError::new(format!(
+            "Can't take the length of a {}.",
+            value.type_name()
+        ))),
+    }
+}
+
+/// Appends an element to the end of a list.
+fn push(interpreter: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    let list = expect_list(&args[0])?;
+    interpreter.allocate(std::mem::size_of::<Value>())?;
+    list.borrow_mut().push(args[1].clone());
+    Ok(Value::Nil)
+}
+
+/// Removes and returns the last element of a list.
+fn pop(_: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    let list = expect_list(&args[0])?;
+    let element = list.borrow_mut().pop();
+    element.ok_or_else(|| RuntimeError::new("Can't pop from an empty list."))
+}
+
+fn expect_list(value: &Value) -> Result<&Rc<RefCell<Vec<Value>>>, RuntimeError> {
+    match value {
+        Value::List(list) => Ok(list),
+        value => Err(RuntimeError::new(format!(
+            "Expected a list but got {}.",
+            value.type_name()
+        ))),
+    }
+}
+
+/// The keys of a map as a new list, in insertion order.
+fn keys(interpreter: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    let map = expect_map(&args[0])?.borrow();
+    interpreter.allocate(map.len() * std::mem::size_of::<Value>())?;
+    Ok(Value::list(
+        map.iter().map(|(key, _)| key.to_value()).collect(),
+    ))
+}
+
+/// The values of a map as a new list, in the order of their keys.
+fn values(interpreter: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    let map = expect_map(&args[0])?.borrow();
+    interpreter.allocate(map.len() * std::mem::size_of::<Value>())?;
+    Ok(Value::list(
+        map.iter().map(|(_, value)| value.clone()).collect(),
+    ))
+}
+
+fn has(_: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    let map = expect_map(&args[0])?;
+    let key = MapKey::new(&args[1])?;
+    let has = map.borrow().contains_key(&key);
+    Ok(Value::Bool(has))
+}
+
+/// Removes a key from a map and returns its value, or `nil` if it was not
+/// there.
+fn remove(_: &mut Interpreter, args: &[Value]) -> Result<Value, RuntimeError> {
+    let map = expect_map(&args[0])?;
+    let key = MapKey::new(&args[1])?;
+    let removed = map.borrow_mut().remove(&key);
+    Ok(removed.unwrap_or(Value::Nil))
+}
+
+fn expect_map(value: &Value) -> Result<&Rc<RefCell<Map>>, RuntimeError> {
+    match value {
+        Value::Map(map) => Ok(map),
+        value => Err(RuntimeError::new(format!(
+            "Expected a map but got {}.",
+            value.type_name()
+        ))),
+    }
+}
@@ -0,0 +1,313 @@
+use crate::ast::{self, AstNode};
+use crate::green::{NodeOrToken, SyntaxElement, SyntaxNode, SyntaxToken};
+use crate::interpreter::Interpreter;
+use crate::kinds::SyntaxKind;
+use crate::scanner;
+use crate::value::Value;
+
+/// Simplifies a syntax tree before it is interpreted. Hosts can have
+/// [`Lox`](crate::Lox) run it on every script with `set_optimize`.
+///
+/// Constant operands are folded with the interpreter's own operator
+/// semantics, so an expression that would fail at runtime (`1 + "a"`) is left
+/// untouched and still fails the same way when executed.
+#[derive(Default)]
+pub struct Optimizer {
+    debug: bool,
+    folds: Vec<(SyntaxNode, SyntaxNode)>,
+}
+
+impl Optimizer {
+    /// In debug mode every replaced node is recorded, for callers to report
+    /// from [`folds`](Optimizer::folds).
+    pub fn new(debug: bool) -> Optimizer {
+        Optimizer {
+            debug,
+            folds: Vec::new(),
+        }
+    }
+
+    /// The `(before, after)` pairs replaced so far. Only recorded in debug mode.
+    pub fn folds(&self) -> &[(SyntaxNode, SyntaxNode)] {
+        &self.folds
+    }
+
+    pub fn optimize(&mut self, syntax_node: SyntaxNode) -> SyntaxNode {
+        match syntax_node.kind() {
+            SyntaxKind::UnaryExpr => self.unary(syntax_node),
+            SyntaxKind::BinExpr => self.binary(syntax_node),
+            SyntaxKind::And | SyntaxKind::Or => self.logical(syntax_node, false),
+            SyntaxKind::If => self.if_condition(syntax_node),
+            SyntaxKind::While => self.while_condition(syntax_node),
+            _ => self.children(syntax_node),
+        }
+    }
+
+    fn children(&mut self, syntax_node: SyntaxNode) -> SyntaxNode {
+        let children = syntax_node
+            .children()
+            .map(|child| match child {
+                NodeOrToken::Node(node) => self.optimize(node).into(),
+                token => token,
+            })
+            .collect();
+        SyntaxNode::new(syntax_node.kind(), children)
+    }
+
+    /// Optimizes an expression whose value is only tested for truthiness,
+    /// where `!!x` can be replaced by `x`.
+    fn condition(&mut self, syntax_node: SyntaxNode) -> SyntaxNode {
+        if let Some(operand) = Self::double_negation(&syntax_node) {
+            let simplified = self.condition(operand);
+            return self.fold(syntax_node, simplified);
+        }
+        match syntax_node.kind() {
+            SyntaxKind::And | SyntaxKind::Or => self.logical(syntax_node, true),
+            _ => self.optimize(syntax_node),
+        }
+    }
+
+    fn unary(&mut self, syntax_node: SyntaxNode) -> SyntaxNode {
+        let unary_expr = ast::UnaryExpr::cast(syntax_node.clone()).unwrap();
+        let op = unary_expr.op();
+        let operand = match op.kind() {
+            SyntaxKind::Bang => self.condition(unary_expr.node()),
+            _ => self.optimize(unary_expr.node()),
+        };
+        if let Some(value) = Self::constant(&operand) {
+            if let Some(result) = Interpreter::unary(op.kind(), &value) {
+                if let Some(literal) = Self::literal(&result, &syntax_node) {
+                    return self.fold(syntax_node, literal);
+                }
+            }
+        }
+        SyntaxNode::new(SyntaxKind::UnaryExpr, vec![op.into(), operand.into()])
+    }
+
+    fn binary(&mut self, syntax_node: SyntaxNode) -> SyntaxNode {
+        let bin_expr = ast::BinExpr::cast(syntax_node.clone()).unwrap();
+        let left = self.optimize(bin_expr.left());
+        let op = bin_expr.op();
+        let right = self.optimize(bin_expr.right());
+        if let (Some(left), Some(right)) = (Self::constant(&left), Self::constant(&right)) {
+            if let Some(result) = Interpreter::binary(&left, op.kind(), &right) {
+                if let Some(literal) = Self::literal(&result, &syntax_node) {
+                    return self.fold(syntax_node, literal);
+                }
+            }
+        }
+        SyntaxNode::new(
+            SyntaxKind::BinExpr,
+            vec![left.into(), op.into(), right.into()],
+        )
+    }
+
+    fn logical(&mut self, syntax_node: SyntaxNode, is_condition: bool) -> SyntaxNode {
+        let logical = ast::Logical::cast(syntax_node.clone()).unwrap();
+        let op = logical.op();
+        let (left, right) = if is_condition {
+            (
+                self.condition(logical.left()),
+                self.condition(logical.right()),
+            )
+        } else {
+            (
+                self.optimize(logical.left()),
+                self.optimize(logical.right()),
+            )
+        };
+        if let Some(value) = Self::constant(&left) {
+            let short_circuits = match logical.kind() {
+                SyntaxKind::And => !Interpreter::is_truthy(&value),
+                _ => Interpreter::is_truthy(&value),
+            };
+            let result = if short_circuits { left } else { right };
+            return self.fold(syntax_node, result);
+        }
+        SyntaxNode::new(logical.kind(), vec![left.into(), op.into(), right.into()])
+    }
+
+    fn if_condition(&mut self, syntax_node: SyntaxNode) -> SyntaxNode {
+        let if_condition = ast::If::cast(syntax_node.clone()).unwrap();
+        let condition = self.condition(if_condition.condition());
+        let then_branch = self.optimize(if_condition.then_branch());
+        let else_branch = if_condition.else_branch().map(|node| self.optimize(node));
+        if let Some(value) = Self::constant(&condition) {
+            let branch = if Interpreter::is_truthy(&value) {
+                then_branch
+            } else {
+                else_branch.unwrap_or_else(Self::empty_block)
+            };
+            return self.fold(syntax_node, branch);
+        }
+        let mut children = vec![condition.into(), then_branch.into()];
+        children.extend(else_branch.map(SyntaxElement::from));
+        SyntaxNode::new(SyntaxKind::If, children)
+    }
+
+    fn while_condition(&mut self, syntax_node: SyntaxNode) -> SyntaxNode {
+        let while_condition = ast::While::cast(syntax_node.clone()).unwrap();
+        let condition = self.condition(while_condition.condition());
+        if let Some(value) = Self::constant(&condition) {
+            if !Interpreter::is_truthy(&value) {
+                return self.fold(syntax_node, Self::empty_block());
+            }
+        }
+        let body = self.optimize(while_condition.body());
+        SyntaxNode::new(SyntaxKind::While, vec![condition.into(), body.into()])
+    }
+
+    fn fold(&mut self, before: SyntaxNode, after: SyntaxNode) -> SyntaxNode {
+        if self.debug {
+            self.folds.push((before, after.clone()));
+        }
+        after
+    }
+
+    fn double_negation(syntax_node: &SyntaxNode) -> Option<SyntaxNode> {
+        let outer = ast::UnaryExpr::cast(syntax_node.clone())?;
+        let inner = ast::UnaryExpr::cast(outer.node())?;
+        if outer.op().kind() == SyntaxKind::Bang && inner.op().kind() == SyntaxKind::Bang {
+            Some(inner.node())
+        } else {
+            None
+        }
+    }
+
+    fn constant(syntax_node: &SyntaxNode) -> Option<Value> {
+        let literal = ast::Literal::cast(syntax_node.clone())?;
+        Some(Interpreter::literal(&literal.token()))
+    }
+
+    /// Builds a literal for a folded value, placed where the folded expression started.
+    fn literal(value: &Value, folded: &SyntaxNode) -> Option<SyntaxNode> {
+        let offset = folded.first_token().map_or(0, |token| token.offset());
+        let (kind, text) = match value {
+            Value::Bool(true) => (SyntaxKind::True, "true".to_string()),
+            Value::Bool(false) => (SyntaxKind::False, "false".to_string()),
+            Value::Nil => (SyntaxKind::Nil, "nil".to_string()),
+            Value::Number(n) => (SyntaxKind::Number, n.to_string()),
+            Value::String(s) => (SyntaxKind::String, scanner::quote(s)),
+            Value::NativeFunction(_) | Value::List(_) | Value::Map(_) | Value::Module(_) => {
+                return None
+            }
+        };
+        let token = SyntaxToken::new(kind, &text, offset);
+        Some(SyntaxNode::new(SyntaxKind::Literal, vec![token.into()]))
+    }
+
+    fn empty_block() -> SyntaxNode {
+        SyntaxNode::new(SyntaxKind::Block, vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Optimizer;
+    use crate::ast::{self, AstNode};
+    use crate::green::SyntaxNode;
+    use crate::kinds::SyntaxKind;
+    use crate::{Interpreter, Parser, Scanner};
+
+    fn parse(source: &str) -> SyntaxNode {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan().cloned().collect();
+        let mut parser = Parser::new(tokens);
+        parser.parse().unwrap().remove(0)
+    }
+
+    fn check_optimize(source: &str, expected: &str) {
+        let mut optimizer = Optimizer::default();
+        let node = optimizer.optimize(parse(source));
+        assert_eq!(node.to_string(), expected);
+    }
+
+    #[test]
+    fn constant_expressions() {
+        check_optimize("1 + 2 * 3;", "7");
+        check_optimize("-4;", "-4");
+        check_optimize("1 < 2;", "true");
+        check_optimize("!nil;", "true");
+        check_optimize("\"a\" + \"b\";", "\"ab\"");
+        check_optimize("\"a\" == \"a\";", "true");
+        check_optimize("nil or 2;", "2");
+        check_optimize("false and x;", "false");
+        check_optimize("1 / 0 > 1;", "true");
+    }
+
+    #[test]
+    fn runtime_errors_are_not_folded() {
+        let mut optimizer = Optimizer::default();
+        let node = optimizer.optimize(parse("1 + 2 + \"a\";"));
+        assert_eq!(node.kind(), SyntaxKind::BinExpr);
+        assert_eq!(node.to_string(), "3+\"a\"");
+        check_optimize("-\"a\";", "-\"a\"");
+    }
+
+    #[test]
+    fn dead_branches() {
+        let mut optimizer = Optimizer::default();
+        let node = optimizer.optimize(parse("if (false) print 1;"));
+        assert_eq!(node.kind(), SyntaxKind::Block);
+        assert_eq!(node.children().count(), 0);
+
+        let node = optimizer.optimize(parse("if (1 > 2) print 1; else print 2;"));
+        assert_eq!(node.kind(), SyntaxKind::Print);
+
+        let node = optimizer.optimize(parse("while (false) print 1;"));
+        assert_eq!(node.kind(), SyntaxKind::Block);
+
+        let node = optimizer.optimize(parse("while (true) print 1;"));
+        assert_eq!(node.kind(), SyntaxKind::While);
+    }
+
+    #[test]
+    fn double_negation() {
+        let mut optimizer = Optimizer::default();
+        let node = optimizer.optimize(parse("if (!!x) print 1;"));
+        let condition = ast::If::cast(node).unwrap().condition();
+        assert_eq!(condition.kind(), SyntaxKind::Identifier);
+
+        let node = optimizer.optimize(parse("while (!!x and !!!y) print 1;"));
+        let condition = ast::While::cast(node).unwrap().condition();
+        assert_eq!(condition.to_string(), "xand!y");
+
+        // Outside of a condition `!!x` produces a bool, not `x` itself.
+        check_optimize("!!x;", "!!x");
+    }
+
+    #[test]
+    fn debug_reports_folds() {
+        let mut optimizer = Optimizer::new(true);
+        optimizer.optimize(parse("1 + 2 * 3;"));
+        let folds = optimizer
+            .folds()
+            .iter()
+            .map(|(before, after)| (before.to_string(), after.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            folds,
+            vec![
+                ("2*3".to_string(), "6".to_string()),
+                ("1+2*3".to_string(), "7".to_string()),
+            ]
+        );
+
+        let mut optimizer = Optimizer::default();
+        optimizer.optimize(parse("1 + 2 * 3;"));
+        assert!(optimizer.folds().is_empty());
+    }
+
+    #[test]
+    fn same_result_as_unoptimized() {
+        for source in &[
+            "1 + 2 * 3 - 4 / 8;",
+            "\"a\" + \"b\" == \"ab\";",
+            "!nil == 1 >= 2;",
+        ] {
+            let expected = Interpreter::default().interpret(parse(source));
+            let optimized = Optimizer::default().optimize(parse(source));
+            assert_eq!(Interpreter::default().interpret(optimized), expected);
+        }
+    }
+}
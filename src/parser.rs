@@ -1,139 +1,418 @@
+use crate::error::SyntaxError;
 use crate::green::{SyntaxElement, SyntaxNode, SyntaxToken};
 use crate::kinds::SyntaxKind;
 
+/// How deeply statements and expressions may nest. Everything that walks
+/// the tree recurses into it, so this keeps hostile scripts from overflowing
+/// the native stack.
+const MAX_DEPTH: usize = 256;
+
+/// What an expression counts for towards [`MAX_DEPTH`]. One nested in
+/// another, like a list element, goes through every precedence level again,
+/// which takes several times the stack of a block or an operator.
+const EXPRESSION_DEPTH: usize = 4;
+
 pub struct Parser {
     tokens: Vec<SyntaxToken>,
     current: usize,
+    /// How many loops the statement being parsed is nested in.
+    loop_depth: usize,
+    /// How deep in the tree the node being parsed is.
+    depth: usize,
+    /// Errors found so far, including ones the parser carried on from
+    /// without skipping anything.
+    errors: Vec<SyntaxError>,
+    /// `///` comments to attach to the declarations they come before.
+    docs: Vec<SyntaxToken>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<SyntaxToken>) -> Parser {
-        Parser { tokens, current: 0 }
+        Parser {
+            tokens,
+            current: 0,
+            loop_depth: 0,
+            depth: 0,
+            errors: Vec::new(),
+            docs: Vec::new(),
+        }
+    }
+
+    /// Attaches the doc comments among `comments` to the declarations right
+    /// after them, as a `Doc` node before the name.
+    pub fn with_docs(mut self, comments: &[SyntaxToken]) -> Parser {
+        self.docs = comments
+            .iter()
+            .filter(|comment| comment.kind() == SyntaxKind::DocComment)
+            .cloned()
+            .collect();
+        self
     }
 
-    pub fn parse(&mut self) -> impl Iterator<Item = SyntaxNode> {
+    /// Parses every statement, or returns all syntax errors found. After an
+    /// error the parser skips to the next statement boundary and carries on.
+    pub fn parse(&mut self) -> Result<Vec<SyntaxNode>, Vec<SyntaxError>> {
         let mut statements = Vec::new();
-        while let Some(_) = self.peek() {
-            statements.push(self.statement());
+        while self.peek().is_some() {
+            let start = self.current;
+            match self.declaration() {
+                Ok(statement) => statements.push(statement),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.depth = 0;
+                    // Always skip the token that failed, so a statement
+                    // that fails without consuming anything can't fail
+                    // again forever.
+                    if self.current == start {
+                        self.advance();
+                    }
+                    self.synchronize();
+                }
+            }
+        }
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(std::mem::take(&mut self.errors))
         }
-        statements.into_iter()
     }
 
-    fn statement(&mut self) -> SyntaxNode {
-        if let Some(token) = self.peek() {
-            let stmt = match token.kind() {
-                SyntaxKind::Print => self.print(),
-                SyntaxKind::Var => self.var_declaration(),
-                SyntaxKind::LeftBrace => self.block(),
-                SyntaxKind::If => self.if_condition(),
-                SyntaxKind::While => self.while_condition(),
-                _ => self.expression_stmt(),
-            };
-            return stmt;
+    fn synchronize(&mut self) {
+        while let Some(token) = self.peek() {
+            match token.kind() {
+                SyntaxKind::Semicolon => {
+                    self.advance();
+                    return;
+                }
+                // Only keywords a statement can start with; stopping at
+                // one none does, like `fun`, would just fail again.
+                SyntaxKind::Var
+                | SyntaxKind::For
+                | SyntaxKind::If
+                | SyntaxKind::While
+                | SyntaxKind::Print
+                | SyntaxKind::Throw
+                | SyntaxKind::Try
+                | SyntaxKind::Import
+                | SyntaxKind::Export => return,
+                _ => self.advance(),
+            }
         }
-        panic!("No more tokens left.");
     }
 
-    fn while_condition(&mut self) -> SyntaxNode {
-        self.consume(SyntaxKind::While, "Expect 'while' keyword");
-        self.consume(SyntaxKind::LeftParen, "Expect '(' after 'if'");
-        let condition = self.expression();
-        self.consume(SyntaxKind::RightParen, "Expect ')' after 'if' condition");
+    /// A statement at the top level of a module, where it may be exported.
+    fn declaration(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        match self.next_if(SyntaxKind::Export) {
+            Some(export) => {
+                if self
+                    .peek()
+                    .is_none_or(|token| token.kind() != SyntaxKind::Var)
+                {
+                    return Err(self.error_at_current("Expect 'var' after 'export'."));
+                }
+                let var_declaration = self.var_declaration()?;
+                Ok(SyntaxNode::new(
+                    SyntaxKind::Export,
+                    vec![export.into(), var_declaration.into()],
+                ))
+            }
+            None => self.statement(),
+        }
+    }
+
+    fn statement(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let token = self.peek_or_error("Expect statement.")?;
+        let depth = self.nest(1)?;
+        let statement = match token.kind() {
+            SyntaxKind::Export => {
+                let error = "Can only export top-level declarations.";
+                self.errors.push(SyntaxError::at_token(error, &token));
+                self.declaration()
+            }
+            SyntaxKind::Import => self.import(),
+            SyntaxKind::Print => self.print(),
+            SyntaxKind::Var => self.var_declaration(),
+            SyntaxKind::LeftBrace => self.block(),
+            SyntaxKind::If => self.if_condition(),
+            SyntaxKind::While => self.while_condition(),
+            SyntaxKind::For => self.for_loop(),
+            SyntaxKind::Break | SyntaxKind::Continue => self.jump(),
+            SyntaxKind::Throw => self.throw(),
+            SyntaxKind::Try => self.try_statement(),
+            _ => self.expression_stmt(),
+        };
+        self.depth = depth;
+        statement
+    }
+
+    fn loop_body(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        self.loop_depth += 1;
         let body = self.statement();
-        SyntaxNode::new(SyntaxKind::While, vec![condition.into(), body.into()])
+        self.loop_depth -= 1;
+        body
+    }
+
+    fn jump(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let token = self.peek_or_error("Expect statement.")?;
+        // The statement is still well formed, so there is nothing to skip.
+        if self.loop_depth == 0 {
+            let message = match token.kind() {
+                SyntaxKind::Break => "Can't use 'break' outside of a loop.",
+                _ => "Can't use 'continue' outside of a loop.",
+            };
+            self.errors.push(SyntaxError::at_token(message, &token));
+        }
+        self.advance();
+        let message = match token.kind() {
+            SyntaxKind::Break => "Expect ';' after 'break'.",
+            _ => "Expect ';' after 'continue'.",
+        };
+        self.consume(SyntaxKind::Semicolon, message)?;
+        Ok(SyntaxNode::new(token.kind(), vec![token.into()]))
+    }
+
+    /// `for (initializer; condition; increment) body`, where an omitted
+    /// clause is an empty `Nil` node.
+    fn for_loop(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let keyword = self.consume(SyntaxKind::For, "Expect 'for' keyword")?;
+        self.consume(SyntaxKind::LeftParen, "Expect '(' after 'for'.")?;
+        let initializer = match self.peek().map(|token| token.kind()) {
+            Some(SyntaxKind::Semicolon) => {
+                self.advance();
+                Self::omitted()
+            }
+            Some(SyntaxKind::Var) => self.var_declaration()?,
+            _ => self.expression_stmt()?,
+        };
+        let condition = match self.peek().map(|token| token.kind()) {
+            Some(SyntaxKind::Semicolon) => Self::omitted(),
+            _ => self.expression()?,
+        };
+        self.consume(SyntaxKind::Semicolon, "Expect ';' after loop condition.")?;
+        let increment = match self.peek().map(|token| token.kind()) {
+            Some(SyntaxKind::RightParen) => Self::omitted(),
+            _ => self.expression()?,
+        };
+        self.consume(SyntaxKind::RightParen, "Expect ')' after for clauses.")?;
+        let body = self.loop_body()?;
+        Ok(SyntaxNode::new(
+            SyntaxKind::For,
+            vec![
+                keyword.into(),
+                initializer.into(),
+                condition.into(),
+                increment.into(),
+                body.into(),
+            ],
+        ))
     }
 
-    fn if_condition(&mut self) -> SyntaxNode {
-        self.consume(SyntaxKind::If, "Expect 'if' keyword");
-        self.consume(SyntaxKind::LeftParen, "Expect '(' after 'if'");
-        let condition = self.expression();
-        self.consume(SyntaxKind::RightParen, "Expect ')' after 'if' condition");
-        let then_branch = self.statement();
+    fn omitted() -> SyntaxNode {
+        SyntaxNode::new(SyntaxKind::Nil, vec![])
+    }
+
+    fn while_condition(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        self.consume(SyntaxKind::While, "Expect 'while' keyword")?;
+        self.consume(SyntaxKind::LeftParen, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(SyntaxKind::RightParen, "Expect ')' after condition.")?;
+        let body = self.loop_body()?;
+        Ok(SyntaxNode::new(
+            SyntaxKind::While,
+            vec![condition.into(), body.into()],
+        ))
+    }
+
+    fn if_condition(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        self.consume(SyntaxKind::If, "Expect 'if' keyword")?;
+        self.consume(SyntaxKind::LeftParen, "Expect '(' after 'if'.")?;
+        let condition = self.expression()?;
+        self.consume(SyntaxKind::RightParen, "Expect ')' after if condition.")?;
+        let then_branch = self.statement()?;
         if let Some(token) = self.peek() {
             if let SyntaxKind::Else = token.kind() {
                 self.advance();
-                let else_branch = self.statement();
-                return SyntaxNode::new(
+                let else_branch = self.statement()?;
+                return Ok(SyntaxNode::new(
                     SyntaxKind::If,
                     vec![condition.into(), then_branch.into(), else_branch.into()],
-                );
+                ));
             }
         }
-        SyntaxNode::new(SyntaxKind::If, vec![condition.into(), then_branch.into()])
+        Ok(SyntaxNode::new(
+            SyntaxKind::If,
+            vec![condition.into(), then_branch.into()],
+        ))
     }
 
-    fn block(&mut self) -> SyntaxNode {
-        let mut stmts = Vec::new();
-        self.consume(SyntaxKind::LeftBrace, "Expect '{' before block");
+    fn block(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let mut children = Vec::new();
+        let brace = self.consume(SyntaxKind::LeftBrace, "Expect '{' before block.")?;
+        children.push(brace.into());
         while let Some(token) = self.peek() {
             match token.kind() {
                 SyntaxKind::RightBrace => break,
-                _ => stmts.push(self.statement()),
+                _ => children.push(self.statement()?.into()),
             }
         }
-        self.consume(SyntaxKind::RightBrace, "Expect '}' after block");
-        let stmts = stmts
-            .into_iter()
-            .map(|stmt| stmt.into())
-            .collect::<Vec<SyntaxElement>>();
-        SyntaxNode::new(SyntaxKind::Block, stmts)
+        let brace = self.consume(SyntaxKind::RightBrace, "Expect '}' after block.")?;
+        children.push(brace.into());
+        Ok(SyntaxNode::new(SyntaxKind::Block, children))
     }
 
-    fn var_declaration(&mut self) -> SyntaxNode {
-        assert_eq!(self.peek().unwrap().kind(), SyntaxKind::Var);
-        self.consume(SyntaxKind::Var, "Expect 'Var' keyword");
-        let ident = self.consume(SyntaxKind::Identifier, "Expect an Identifier");
-        let initializer = match self.peek().unwrap().kind() {
-            SyntaxKind::Equal => {
+    fn var_declaration(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let doc = self.doc();
+        self.consume(SyntaxKind::Var, "Expect 'Var' keyword")?;
+        let ident = self.consume(SyntaxKind::Identifier, "Expect variable name.")?;
+        let initializer = match self.peek().map(|token| token.kind()) {
+            Some(SyntaxKind::Equal) => {
                 self.advance();
-                self.expression()
+                self.expression()?
             }
-            _ => SyntaxNode::new(SyntaxKind::Nil, vec![]),
+            _ => Self::omitted(),
         };
-        self.consume(SyntaxKind::Semicolon, "Expect ';' after value.");
-        SyntaxNode::new(SyntaxKind::Var, vec![ident.into(), initializer.into()])
+        self.consume(
+            SyntaxKind::Semicolon,
+            "Expect ';' after variable declaration.",
+        )?;
+        let children = doc
+            .into_iter()
+            .map(SyntaxElement::from)
+            .chain([ident.into(), initializer.into()])
+            .collect();
+        Ok(SyntaxNode::new(SyntaxKind::Var, children))
     }
 
-    fn print(&mut self) -> SyntaxNode {
-        let token = self.peek().unwrap();
-        assert_eq!(token.kind(), SyntaxKind::Print);
-        self.advance();
-        let expr = self.expression();
-        self.consume(SyntaxKind::Semicolon, "Expect ';' after value.");
-        SyntaxNode::new(SyntaxKind::Print, vec![token.into(), expr.into()])
+    /// The doc comments between the previous token and the declaration at
+    /// the current one, including its `export`.
+    fn doc(&self) -> Option<SyntaxNode> {
+        let mut start = self.current;
+        if start > 0 && self.tokens[start - 1].kind() == SyntaxKind::Export {
+            start -= 1;
+        }
+        let end = self.tokens.get(start)?.offset();
+        let after = start
+            .checked_sub(1)
+            .map_or(0, |previous| self.tokens[previous].span().end);
+        let docs = self
+            .docs
+            .iter()
+            .filter(|doc| (after..end).contains(&doc.offset()))
+            .cloned()
+            .map(SyntaxElement::from)
+            .collect::<Vec<_>>();
+        if docs.is_empty() {
+            None
+        } else {
+            Some(SyntaxNode::new(SyntaxKind::Doc, docs))
+        }
     }
 
-    fn expression_stmt(&mut self) -> SyntaxNode {
-        let expression = self.expression();
-        self.consume(SyntaxKind::Semicolon, "Expect ';' after expression.");
-        expression
+    /// `import "path" as name;`
+    fn import(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let token = self.consume(SyntaxKind::Import, "Expect 'import' keyword")?;
+        let path = self.consume(SyntaxKind::String, "Expect module path after 'import'.")?;
+        let as_token = self.consume(SyntaxKind::As, "Expect 'as' after module path.")?;
+        let name = self.consume(SyntaxKind::Identifier, "Expect module name after 'as'.")?;
+        self.consume(SyntaxKind::Semicolon, "Expect ';' after import.")?;
+        Ok(SyntaxNode::new(
+            SyntaxKind::Import,
+            vec![token.into(), path.into(), as_token.into(), name.into()],
+        ))
     }
 
-    fn expression(&mut self) -> SyntaxNode {
+    fn throw(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let token = self.consume(SyntaxKind::Throw, "Expect 'throw' keyword")?;
+        let expr = self.expression()?;
+        self.consume(SyntaxKind::Semicolon, "Expect ';' after thrown value.")?;
+        Ok(SyntaxNode::new(
+            SyntaxKind::Throw,
+            vec![token.into(), expr.into()],
+        ))
+    }
+
+    /// `try block`, followed by `catch (name) block`, `finally block` or
+    /// both, each clause in a node of its own.
+    fn try_statement(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let token = self.consume(SyntaxKind::Try, "Expect 'try' keyword")?;
+        let mut children = vec![token.into(), self.block()?.into()];
+        if let Some(catch) = self.next_if(SyntaxKind::Catch) {
+            self.consume(SyntaxKind::LeftParen, "Expect '(' after 'catch'.")?;
+            let name = self.consume(SyntaxKind::Identifier, "Expect error variable name.")?;
+            self.consume(SyntaxKind::RightParen, "Expect ')' after error variable.")?;
+            let body = self.block()?;
+            children.push(
+                SyntaxNode::new(
+                    SyntaxKind::Catch,
+                    vec![catch.into(), name.into(), body.into()],
+                )
+                .into(),
+            );
+        }
+        if let Some(finally) = self.next_if(SyntaxKind::Finally) {
+            let body = self.block()?;
+            children.push(
+                SyntaxNode::new(SyntaxKind::Finally, vec![finally.into(), body.into()]).into(),
+            );
+        }
+        if children.len() == 2 {
+            return Err(self.error_at_current("Expect 'catch' or 'finally' after try block."));
+        }
+        Ok(SyntaxNode::new(SyntaxKind::Try, children))
+    }
+
+    fn print(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let token = self.consume(SyntaxKind::Print, "Expect 'print' keyword")?;
+        let expr = self.expression()?;
+        self.consume(SyntaxKind::Semicolon, "Expect ';' after value.")?;
+        Ok(SyntaxNode::new(
+            SyntaxKind::Print,
+            vec![token.into(), expr.into()],
+        ))
+    }
+
+    fn expression_stmt(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let expression = self.expression()?;
+        self.consume(SyntaxKind::Semicolon, "Expect ';' after expression.")?;
+        Ok(expression)
+    }
+
+    fn expression(&mut self) -> Result<SyntaxNode, SyntaxError> {
         self.assignment()
     }
 
-    fn assignment(&mut self) -> SyntaxNode {
-        let var = self.or();
+    fn assignment(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let depth = self.nest(EXPRESSION_DEPTH)?;
+        let var = self.or()?;
 
         if let Some(token) = self.peek() {
             if token.kind() == SyntaxKind::Equal {
                 self.advance();
-                let value = self.assignment();
-                return SyntaxNode::new(SyntaxKind::Assign, vec![var.into(), value.into()]);
+                let value = self.assignment()?;
+                if !matches!(var.kind(), SyntaxKind::Identifier | SyntaxKind::Index) {
+                    return Err(SyntaxError::at_token("Invalid assignment target.", &token));
+                }
+                self.depth = depth;
+                return Ok(SyntaxNode::new(
+                    SyntaxKind::Assign,
+                    vec![var.into(), value.into()],
+                ));
             }
         }
-        var
+        self.depth = depth;
+        Ok(var)
     }
 
-    fn or(&mut self) -> SyntaxNode {
-        let mut left = self.and();
+    fn or(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let depth = self.depth;
+        let mut left = self.and()?;
 
         while let Some(token) = self.peek() {
             match token.kind() {
                 SyntaxKind::Or => {
+                    self.nest(1)?;
                     self.advance();
-                    let right = self.and();
+                    let right = self.and()?;
                     left = SyntaxNode::new(
                         SyntaxKind::Or,
                         vec![left.into(), token.into(), right.into()],
@@ -142,17 +421,20 @@ impl Parser {
                 _ => break,
             }
         }
-        left
+        self.depth = depth;
+        Ok(left)
     }
 
-    fn and(&mut self) -> SyntaxNode {
-        let mut left = self.equality();
+    fn and(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let depth = self.depth;
+        let mut left = self.equality()?;
 
         while let Some(token) = self.peek() {
             match token.kind() {
                 SyntaxKind::And => {
+                    self.nest(1)?;
                     self.advance();
-                    let right = self.equality();
+                    let right = self.equality()?;
                     left = SyntaxNode::new(
                         SyntaxKind::And,
                         vec![left.into(), token.into(), right.into()],
@@ -161,17 +443,20 @@ impl Parser {
                 _ => break,
             }
         }
-        left
+        self.depth = depth;
+        Ok(left)
     }
 
-    fn equality(&mut self) -> SyntaxNode {
-        let mut left = self.comparison();
+    fn equality(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let depth = self.depth;
+        let mut left = self.comparison()?;
 
         while let Some(token) = self.peek() {
             match token.kind() {
                 SyntaxKind::BangEqual | SyntaxKind::EqualEqual => {
+                    self.nest(1)?;
                     self.advance();
-                    let right = self.comparison();
+                    let right = self.comparison()?;
                     left = SyntaxNode::new(
                         SyntaxKind::BinExpr,
                         vec![left.into(), token.into(), right.into()],
@@ -180,11 +465,13 @@ impl Parser {
                 _ => break,
             }
         }
-        left
+        self.depth = depth;
+        Ok(left)
     }
 
-    fn comparison(&mut self) -> SyntaxNode {
-        let mut left = self.term();
+    fn comparison(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let depth = self.depth;
+        let mut left = self.term()?;
 
         while let Some(token) = self.peek() {
             match token.kind() {
@@ -192,8 +479,9 @@ impl Parser {
                 | SyntaxKind::GreaterEqual
                 | SyntaxKind::Less
                 | SyntaxKind::LessEqual => {
+                    self.nest(1)?;
                     self.advance();
-                    let right = self.term();
+                    let right = self.term()?;
                     left = SyntaxNode::new(
                         SyntaxKind::BinExpr,
                         vec![left.into(), token.into(), right.into()],
@@ -202,17 +490,20 @@ impl Parser {
                 _ => break,
             }
         }
-        left
+        self.depth = depth;
+        Ok(left)
     }
 
-    fn term(&mut self) -> SyntaxNode {
-        let mut left = self.factor();
+    fn term(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let depth = self.depth;
+        let mut left = self.factor()?;
 
         while let Some(token) = self.peek() {
             match token.kind() {
                 SyntaxKind::Minus | SyntaxKind::Plus => {
+                    self.nest(1)?;
                     self.advance();
-                    let right = self.factor();
+                    let right = self.factor()?;
                     left = SyntaxNode::new(
                         SyntaxKind::BinExpr,
                         vec![left.into(), token.into(), right.into()],
@@ -221,17 +512,20 @@ impl Parser {
                 _ => break,
             }
         }
-        left
+        self.depth = depth;
+        Ok(left)
     }
 
-    fn factor(&mut self) -> SyntaxNode {
-        let mut left = self.unary();
+    fn factor(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let depth = self.depth;
+        let mut left = self.unary()?;
 
         while let Some(token) = self.peek() {
             match token.kind() {
                 SyntaxKind::Slash | SyntaxKind::Star => {
+                    self.nest(1)?;
                     self.advance();
-                    let right = self.unary();
+                    let right = self.unary()?;
                     left = SyntaxNode::new(
                         SyntaxKind::BinExpr,
                         vec![left.into(), token.into(), right.into()],
@@ -240,57 +534,351 @@ impl Parser {
                 _ => break,
             }
         }
-        left
+        self.depth = depth;
+        Ok(left)
     }
 
-    fn unary(&mut self) -> SyntaxNode {
-        if let Some(token) = self.peek() {
-            let node = match token.kind() {
-                SyntaxKind::Bang | SyntaxKind::Minus => {
+    fn unary(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let token = self.peek_or_error("Expect expression.")?;
+        match token.kind() {
+            SyntaxKind::Bang | SyntaxKind::Minus => {
+                let depth = self.nest(1)?;
+                self.advance();
+                let right = self.unary()?;
+                self.depth = depth;
+                Ok(SyntaxNode::new(
+                    SyntaxKind::UnaryExpr,
+                    vec![token.into(), right.into()],
+                ))
+            }
+            _ => self.call(),
+        }
+    }
+
+    fn call(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let depth = self.depth;
+        let mut expr = self.primary()?;
+
+        while let Some(token) = self.peek() {
+            if matches!(
+                token.kind(),
+                SyntaxKind::LeftParen | SyntaxKind::Dot | SyntaxKind::LeftBracket
+            ) {
+                self.nest(1)?;
+            }
+            match token.kind() {
+                SyntaxKind::LeftParen => {
                     self.advance();
-                    let right = self.unary();
-                    SyntaxNode::new(SyntaxKind::UnaryExpr, vec![token.into(), right.into()])
+                    expr = self.finish_call(expr)?;
                 }
-                _ => self.primary(),
-            };
-            return node;
+                SyntaxKind::Dot => {
+                    self.advance();
+                    let name =
+                        self.consume(SyntaxKind::Identifier, "Expect property name after '.'.")?;
+                    expr = SyntaxNode::new(
+                        SyntaxKind::Get,
+                        vec![expr.into(), token.into(), name.into()],
+                    );
+                }
+                SyntaxKind::LeftBracket => {
+                    self.advance();
+                    let index = self.expression()?;
+                    let bracket =
+                        self.consume(SyntaxKind::RightBracket, "Expect ']' after index.")?;
+                    expr = SyntaxNode::new(
+                        SyntaxKind::Index,
+                        vec![expr.into(), token.into(), index.into(), bracket.into()],
+                    );
+                }
+                _ => break,
+            }
         }
-        panic!("No more tokens left");
+        self.depth = depth;
+        Ok(expr)
     }
 
-    fn primary(&mut self) -> SyntaxNode {
+    fn finish_call(&mut self, callee: SyntaxNode) -> Result<SyntaxNode, SyntaxError> {
+        let mut children = vec![callee.into()];
         if let Some(token) = self.peek() {
-            self.advance();
-            let node = match token.kind() {
-                SyntaxKind::False
-                | SyntaxKind::True
-                | SyntaxKind::Nil
-                | SyntaxKind::Number
-                | SyntaxKind::String => SyntaxNode::new(SyntaxKind::Literal, vec![token.into()]),
-                SyntaxKind::Identifier => {
-                    SyntaxNode::new(SyntaxKind::Identifier, vec![token.into()])
+            if token.kind() != SyntaxKind::RightParen {
+                loop {
+                    children.push(self.expression()?.into());
+                    match self.peek() {
+                        Some(token) if token.kind() == SyntaxKind::Comma => self.advance(),
+                        _ => break,
+                    }
                 }
-                _ => panic!("{:?} unimplemented", token.kind()),
-            };
-            return node;
+            }
         }
-        panic!("No more tokens left");
+        let paren = self.consume(SyntaxKind::RightParen, "Expect ')' after arguments.")?;
+        children.push(paren.into());
+        Ok(SyntaxNode::new(SyntaxKind::Call, children))
+    }
+
+    fn list(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let bracket = self.consume(SyntaxKind::LeftBracket, "Expect '[' before list.")?;
+        let mut children = vec![bracket.into()];
+        if self
+            .peek()
+            .is_some_and(|token| token.kind() != SyntaxKind::RightBracket)
+        {
+            loop {
+                children.push(self.expression()?.into());
+                match self.peek() {
+                    Some(token) if token.kind() == SyntaxKind::Comma => self.advance(),
+                    _ => break,
+                }
+            }
+        }
+        let bracket = self.consume(SyntaxKind::RightBracket, "Expect ']' after list elements.")?;
+        children.push(bracket.into());
+        Ok(SyntaxNode::new(SyntaxKind::ListLiteral, children))
+    }
+
+    /// A map literal. Braces only start one in expression position: at the
+    /// start of a statement they start a block.
+    fn map(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let brace = self.consume(SyntaxKind::LeftBrace, "Expect '{' before map.")?;
+        let mut children = vec![brace.into()];
+        if self
+            .peek()
+            .is_some_and(|token| token.kind() != SyntaxKind::RightBrace)
+        {
+            loop {
+                children.push(self.expression()?.into());
+                self.consume(SyntaxKind::Colon, "Expect ':' after map key.")?;
+                children.push(self.expression()?.into());
+                match self.peek() {
+                    Some(token) if token.kind() == SyntaxKind::Comma => self.advance(),
+                    _ => break,
+                }
+            }
+        }
+        let brace = self.consume(SyntaxKind::RightBrace, "Expect '}' after map entries.")?;
+        children.push(brace.into());
+        Ok(SyntaxNode::new(SyntaxKind::MapLiteral, children))
+    }
+
+    /// A string with `${}` interpolations: its pieces with the interpolated
+    /// expressions between them.
+    fn interpolation(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let head = self.consume(SyntaxKind::StringHead, "Expect string.")?;
+        let mut children = vec![head.into()];
+        loop {
+            children.push(self.expression()?.into());
+            let error = "Expect '}' after interpolated expression.";
+            let token = self.peek_or_error(error)?;
+            match token.kind() {
+                SyntaxKind::StringMiddle => children.push(token.into()),
+                SyntaxKind::StringTail => {
+                    children.push(token.into());
+                    self.advance();
+                    return Ok(SyntaxNode::new(SyntaxKind::Interpolation, children));
+                }
+                _ => return Err(SyntaxError::at_token(error, &token)),
+            }
+            self.advance();
+        }
+    }
+
+    fn primary(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let token = self.peek_or_error("Expect expression.")?;
+        let node = match token.kind() {
+            SyntaxKind::LeftBracket => return self.list(),
+            SyntaxKind::LeftBrace => return self.map(),
+            SyntaxKind::StringHead => return self.interpolation(),
+            SyntaxKind::False
+            | SyntaxKind::True
+            | SyntaxKind::Nil
+            | SyntaxKind::Number
+            | SyntaxKind::String => SyntaxNode::new(SyntaxKind::Literal, vec![token.into()]),
+            SyntaxKind::Identifier => SyntaxNode::new(SyntaxKind::Identifier, vec![token.into()]),
+            _ => return Err(SyntaxError::at_token("Expect expression.", &token)),
+        };
+        self.advance();
+        Ok(node)
     }
 
     fn peek(&self) -> Option<SyntaxToken> {
         self.tokens.get(self.current).cloned()
     }
 
+    /// Like [`peek`](Parser::peek), but running out of tokens is an error.
+    fn peek_or_error(&self, error: &'static str) -> Result<SyntaxToken, SyntaxError> {
+        self.peek().ok_or_else(|| self.error_at_end(error))
+    }
+
     fn advance(&mut self) {
         self.current += 1;
     }
 
-    fn consume(&mut self, kind: SyntaxKind, error: &'static str) -> SyntaxToken {
-        let token = self.peek().expect(error);
+    /// Consumes the next token if it is a `kind`.
+    fn next_if(&mut self, kind: SyntaxKind) -> Option<SyntaxToken> {
+        let token = self.peek().filter(|token| token.kind() == kind)?;
+        self.advance();
+        Some(token)
+    }
+
+    fn consume(
+        &mut self,
+        kind: SyntaxKind,
+        error: &'static str,
+    ) -> Result<SyntaxToken, SyntaxError> {
+        let token = self.peek_or_error(error)?;
         if token.kind() != kind {
-            panic!("{}", error);
+            return Err(SyntaxError::at_token(error, &token));
         }
         self.advance();
-        token
+        Ok(token)
+    }
+
+    /// An error at the next token, or at the end if there is none.
+    /// Goes `levels` deeper into the tree, returning the depth to go back to
+    /// once the node is parsed.
+    fn nest(&mut self, levels: usize) -> Result<usize, SyntaxError> {
+        let depth = self.depth;
+        if depth + levels > MAX_DEPTH {
+            return Err(self.error_at_current("Too much nesting."));
+        }
+        self.depth += levels;
+        Ok(depth)
+    }
+
+    fn error_at_current(&self, error: &'static str) -> SyntaxError {
+        match self.peek() {
+            Some(token) => SyntaxError::at_token(error, &token),
+            None => self.error_at_end(error),
+        }
+    }
+
+    fn error_at_end(&self, error: &'static str) -> SyntaxError {
+        let end = self.tokens.last().map_or(0, |token| token.span().end);
+        SyntaxError::at_end(error, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Parser;
+    use crate::ast::{self, AstNode};
+    use crate::green::SyntaxElement;
+    use crate::Scanner;
+
+    fn parse_errors(source: &str) -> Vec<String> {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan().cloned().collect();
+        let mut parser = Parser::new(tokens);
+        let errors = parser.parse().unwrap_err();
+        errors.iter().map(|error| error.to_string()).collect()
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse_errors("print 1"),
+            vec!["Error at end: Expect ';' after value."]
+        );
+        assert_eq!(
+            parse_errors("var 1 = 2;"),
+            vec!["Error at '1': Expect variable name."]
+        );
+        assert_eq!(
+            parse_errors("1 = 2;"),
+            vec!["Error at '=': Invalid assignment target."]
+        );
+        assert_eq!(
+            parse_errors("print ;"),
+            vec!["Error at ';': Expect expression."]
+        );
+        assert_eq!(
+            parse_errors("while (a) { if (b) break; } break; { continue; }"),
+            vec![
+                "Error at 'break': Can't use 'break' outside of a loop.",
+                "Error at 'continue': Can't use 'continue' outside of a loop.",
+            ]
+        );
+    }
+
+    #[test]
+    fn recovers_at_statement_boundaries() {
+        assert_eq!(
+            parse_errors("print + 1; var a = 1; if a) print a; print a"),
+            vec![
+                "Error at '+': Expect expression.",
+                "Error at 'a': Expect '(' after 'if'.",
+                "Error at end: Expect ';' after value.",
+            ]
+        );
+    }
+
+    #[test]
+    fn recovers_from_unsupported_keywords() {
+        assert_eq!(
+            parse_errors("fun; class; return; while (true) { fun; }"),
+            vec![
+                "Error at 'fun': Expect expression.",
+                "Error at 'class': Expect expression.",
+                "Error at 'return': Expect expression.",
+                "Error at 'fun': Expect expression.",
+                "Error at '}': Expect expression.",
+            ]
+        );
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let blocks = |n| format!("{}{}", "{".repeat(n), "}".repeat(n));
+        let mut scanner = Scanner::new(&blocks(256));
+        let tokens = scanner.scan().cloned().collect();
+        assert!(Parser::new(tokens).parse().is_ok());
+        assert_eq!(
+            parse_errors(&blocks(257)),
+            vec!["Error at '{': Too much nesting."]
+        );
+        // Deeper input fails the same way instead of overflowing the stack,
+        // whether it nests through statements, operators or expressions.
+        assert_eq!(
+            parse_errors(&blocks(50_000)),
+            vec!["Error at '{': Too much nesting."]
+        );
+        assert_eq!(
+            parse_errors(&format!("print {}1;", "-".repeat(100_000))),
+            vec!["Error at '-': Too much nesting."]
+        );
+        assert_eq!(
+            parse_errors(&format!("print 1{};", "+1".repeat(100_000))),
+            vec!["Error at '+': Too much nesting."]
+        );
+        assert_eq!(
+            parse_errors(&format!("print {}1;", "[".repeat(100_000))),
+            vec!["Error at '[': Too much nesting."]
+        );
+    }
+
+    #[test]
+    fn doc_comments() {
+        let source =
+            "/// a\n/// b\nvar a;\n/// stray\nprint 1; { /* c */ /// d\n var d = 1; } var e;";
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan().cloned().collect();
+        let mut parser = Parser::new(tokens).with_docs(scanner.comments());
+        let statements = parser.parse().unwrap();
+        let mut docs = Vec::new();
+        let mut pending = statements;
+        while let Some(statement) = pending.pop() {
+            if let Some(var_declaration) = ast::VarDeclaration::cast(statement.clone()) {
+                docs.push((var_declaration.ident().to_string(), var_declaration.doc()));
+            }
+            pending.extend(statement.children().filter_map(SyntaxElement::into_node));
+        }
+        docs.sort();
+        assert_eq!(
+            docs,
+            vec![
+                ("a".to_string(), Some("a\nb".to_string())),
+                ("d".to_string(), Some("d".to_string())),
+                ("e".to_string(), None),
+            ]
+        );
     }
 }
@@ -0,0 +1,264 @@
+use crate::error::SyntaxError;
+use crate::green::SyntaxNode;
+use crate::kinds::SyntaxKind;
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+use crate::symbols::SymbolTable;
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+/// A replacement of the source in `span` with `new_text`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub span: Range<usize>,
+    pub new_text: String,
+}
+
+impl TextEdit {
+    /// Applies edits that don't overlap to `source`.
+    pub fn apply(source: &str, edits: &[TextEdit]) -> String {
+        let mut edits = edits.iter().collect::<Vec<_>>();
+        edits.sort_by_key(|edit| edit.span.start);
+        let mut out = String::with_capacity(source.len());
+        let mut last_end = 0;
+        for edit in edits {
+            out += &source[last_end..edit.span.start];
+            out += &edit.new_text;
+            last_end = edit.span.end;
+        }
+        out + &source[last_end..]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenameError {
+    Syntax(Vec<SyntaxError>),
+    /// There is no variable declared in the program at the position.
+    NotAVariable,
+    InvalidName(String),
+    /// The renamed program would resolve a name differently, or not at all.
+    /// Spans are in the original source.
+    Conflict(SyntaxError),
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameError::Syntax(errors) => {
+                let messages = errors.iter().map(ToString::to_string);
+                fmt::Display::fmt(&messages.collect::<Vec<_>>().join("\n"), f)
+            }
+            RenameError::NotAVariable => write!(f, "No variable declared at this position."),
+            RenameError::InvalidName(name) => write!(f, "'{}' is not a valid variable name.", name),
+            RenameError::Conflict(error) => write!(f, "{}", error.message()),
+        }
+    }
+}
+
+impl Error for RenameError {}
+
+/// The edits that rename the variable at `offset`, either in its
+/// declaration or in a use of it, to `new_name`.
+///
+/// Variables with the same name in other scopes are left alone. The rename
+/// is refused if it would change which declaration any name in the program
+/// resolves to, like a use of the variable being captured by an inner
+/// declaration of `new_name`.
+pub fn rename(source: &str, offset: usize, new_name: &str) -> Result<Vec<TextEdit>, RenameError> {
+    let statements = parse(source).map_err(RenameError::Syntax)?;
+    let symbols = SymbolTable::new(&statements);
+    let declaration = symbols
+        .declaration_at(offset)
+        .ok_or(RenameError::NotAVariable)?;
+    if !is_identifier(new_name) {
+        return Err(RenameError::InvalidName(new_name.to_string()));
+    }
+
+    let old_name = &symbols.declarations()[declaration].name;
+    let spans = std::iter::once(symbols.declarations()[declaration].name_span.clone()).chain(
+        symbols
+            .references_to(declaration)
+            .map(|reference| reference.span.clone()),
+    );
+    let mut edits = spans
+        .map(|span| TextEdit {
+            span,
+            new_text: new_name.to_string(),
+        })
+        .collect::<Vec<_>>();
+    edits.sort_by_key(|edit| edit.span.start);
+
+    let renamed = parse(&TextEdit::apply(source, &edits)).expect("renaming kept the syntax");
+    let original = |span: Range<usize>| original_span(&edits, span);
+    if let Err(errors) = Resolver::new().resolve(&renamed) {
+        let error = &errors[0];
+        let mut conflict = SyntaxError::new(
+            format!(
+                "Renaming '{}' to '{}' makes the program invalid: {}",
+                old_name,
+                new_name,
+                error.message()
+            ),
+            original(error.span()),
+        );
+        for (span, message) in error.labels() {
+            conflict = conflict.with_label(original(span.clone()), message.clone());
+        }
+        return Err(RenameError::Conflict(conflict));
+    }
+
+    let resolved = SymbolTable::new(&renamed);
+    let changed = symbols
+        .references()
+        .iter()
+        .zip(resolved.references())
+        .find(|(before, after)| before.declaration != after.declaration);
+    if let Some((before, after)) = changed {
+        let mut conflict = SyntaxError::new(
+            format!(
+                "Renaming '{}' to '{}' would change which variable this refers to.",
+                old_name, new_name
+            ),
+            before.span.clone(),
+        );
+        if let Some(capturing) = after.declaration {
+            conflict = conflict.with_label(
+                symbols.declarations()[capturing].name_span.clone(),
+                "it would refer to this declaration instead",
+            );
+        }
+        return Err(RenameError::Conflict(conflict));
+    }
+    Ok(edits)
+}
+
+fn parse(source: &str) -> Result<Vec<SyntaxNode>, Vec<SyntaxError>> {
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan().cloned().collect();
+    if !scanner.errors().is_empty() {
+        return Err(scanner.errors().to_vec());
+    }
+    Parser::new(tokens).parse()
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut scanner = Scanner::new(name);
+    let tokens = scanner.scan().collect::<Vec<_>>();
+    matches!(tokens.as_slice(), [token] if token.kind() == SyntaxKind::Identifier && token.text() == name)
+}
+
+/// Maps a span in the edited source back to the source before `edits`.
+fn original_span(edits: &[TextEdit], span: Range<usize>) -> Range<usize> {
+    let original = |offset: usize| {
+        let mut shift = 0isize;
+        for edit in edits {
+            let new_start = (edit.span.start as isize + shift) as usize;
+            if offset < new_start {
+                break;
+            }
+            let new_end = new_start + edit.new_text.len();
+            if offset < new_end {
+                return edit.span.start;
+            }
+            shift += edit.new_text.len() as isize - edit.span.len() as isize;
+        }
+        (offset as isize - shift) as usize
+    };
+    original(span.start)..original(span.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rename, RenameError, TextEdit};
+
+    fn renamed(source: &str, offset: usize, new_name: &str) -> Result<String, String> {
+        match rename(source, offset, new_name) {
+            Ok(edits) => Ok(TextEdit::apply(source, &edits)),
+            Err(RenameError::Conflict(error)) => Err(format!(
+                "{} {:?} {:?}",
+                error.message(),
+                error.span(),
+                error.labels()
+            )),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    #[test]
+    fn renames_one_binding() {
+        let source = "var a = 1; { var a = 2; print a; } print a; a = a + 1;";
+        assert_eq!(
+            renamed(source, 4, "total"),
+            Ok(
+                "var total = 1; { var a = 2; print a; } print total; total = total + 1;"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            renamed(source, 30, "b"),
+            Ok("var a = 1; { var b = 2; print b; } print a; a = a + 1;".to_string())
+        );
+        assert_eq!(
+            renamed(source, 8, "b"),
+            Err("No variable declared at this position.".to_string())
+        );
+        assert_eq!(
+            renamed("print clock();", 7, "b"),
+            Err("No variable declared at this position.".to_string())
+        );
+        assert_eq!(
+            renamed(source, 4, "while"),
+            Err("'while' is not a valid variable name.".to_string())
+        );
+        assert_eq!(
+            renamed(source, 4, "a b"),
+            Err("'a b' is not a valid variable name.".to_string())
+        );
+    }
+
+    #[test]
+    fn refuses_capture() {
+        //           0         1         2         3
+        //           0123456789012345678901234567890123456
+        let source = "var a = 1; { var b = 2; print a; }";
+        assert_eq!(
+            renamed(source, 4, "b"),
+            Err(
+                "Renaming 'a' to 'b' would change which variable this refers to. \
+                 30..31 [(17..18, \"it would refer to this declaration instead\")]"
+                    .to_string()
+            )
+        );
+        // The other way around, a use of the outer `b` would be captured.
+        let source = "var b = 1; { var a = 2; print b; }";
+        assert_eq!(
+            renamed(source, 17, "b"),
+            Err(
+                "Renaming 'a' to 'b' would change which variable this refers to. \
+                 30..31 [(17..18, \"it would refer to this declaration instead\")]"
+                    .to_string()
+            )
+        );
+        // Natives can be captured too.
+        assert!(renamed("var a = 1; print clock();", 4, "clock").is_err());
+        let source = "var b = 1; { var a = b; }";
+        assert_eq!(
+            renamed(source, 17, "b"),
+            Err("Renaming 'a' to 'b' makes the program invalid: \
+                 Can't read local variable in its own initializer. \
+                 21..22 [(17..18, \"variable declared here\")]"
+                .to_string())
+        );
+        // A later global with the new name captures uses after it.
+        assert_eq!(
+            renamed("var a; var b; print a;", 4, "b"),
+            Err(
+                "Renaming 'a' to 'b' would change which variable this refers to. \
+                 20..21 [(11..12, \"it would refer to this declaration instead\")]"
+                    .to_string()
+            )
+        );
+    }
+}
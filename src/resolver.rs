@@ -0,0 +1,187 @@
+use crate::ast::{self, AstNode};
+use crate::error::SyntaxError;
+use crate::green::{SyntaxElement, SyntaxNode, SyntaxToken};
+use crate::intern::Symbol;
+use crate::kinds::SyntaxKind;
+use std::collections::HashMap;
+
+/// Checks how local variables are declared and used before a program runs.
+#[derive(Default)]
+pub struct Resolver {
+    scopes: Vec<HashMap<Symbol, Local>>,
+    errors: Vec<SyntaxError>,
+}
+
+struct Local {
+    declaration: SyntaxToken,
+    /// False while the variable's own initializer is being resolved.
+    defined: bool,
+}
+
+impl Resolver {
+    pub fn new() -> Resolver {
+        Resolver::default()
+    }
+
+    pub fn resolve(&mut self, statements: &[SyntaxNode]) -> Result<(), Vec<SyntaxError>> {
+        for statement in statements {
+            self.node(statement);
+        }
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    fn node(&mut self, syntax_node: &SyntaxNode) {
+        match syntax_node.kind() {
+            SyntaxKind::Block => self.block(syntax_node),
+            // A variable declared in the initializer is scoped to the loop.
+            SyntaxKind::For => {
+                self.scopes.push(HashMap::new());
+                for child in syntax_node.children().filter_map(SyntaxElement::into_node) {
+                    self.node(&child);
+                }
+                self.scopes.pop();
+            }
+            SyntaxKind::Catch => {
+                let catch = ast::Catch::cast(syntax_node.clone()).unwrap();
+                let name = catch.name();
+                let mut scope = HashMap::new();
+                let local = Local {
+                    declaration: name.clone(),
+                    defined: true,
+                };
+                scope.insert(name.symbol(), local);
+                self.scopes.push(scope);
+                self.node(&catch.body());
+                self.scopes.pop();
+            }
+            SyntaxKind::Var => self.var_declaration(syntax_node),
+            SyntaxKind::Import => {
+                let name = ast::Import::cast(syntax_node.clone()).unwrap().name();
+                self.declare(&name);
+                self.define(&name);
+            }
+            SyntaxKind::Identifier => self.identifier(syntax_node),
+            // A variable target is written, not read, so only the value is
+            // resolved. An element target reads the list it is in.
+            SyntaxKind::Assign => {
+                let assign = ast::Assign::cast(syntax_node.clone()).unwrap();
+                if assign.var_name().is_none() {
+                    self.node(&assign.target());
+                }
+                self.node(&assign.value());
+            }
+            _ => {
+                for child in syntax_node.children().filter_map(SyntaxElement::into_node) {
+                    self.node(&child);
+                }
+            }
+        }
+    }
+
+    fn block(&mut self, syntax_node: &SyntaxNode) {
+        self.scopes.push(HashMap::new());
+        let block = ast::Block::cast(syntax_node.clone()).unwrap();
+        for child in block.children() {
+            self.node(&child);
+        }
+        self.scopes.pop();
+    }
+
+    fn var_declaration(&mut self, syntax_node: &SyntaxNode) {
+        let var_declaration = ast::VarDeclaration::cast(syntax_node.clone()).unwrap();
+        let ident = var_declaration.ident();
+        self.declare(&ident);
+        self.node(&var_declaration.initializer());
+        self.define(&ident);
+    }
+
+    /// Marks a declared local as ready to be read.
+    fn define(&mut self, ident: &SyntaxToken) {
+        if let Some(local) = self
+            .scopes
+            .last_mut()
+            .and_then(|scope| scope.get_mut(&ident.symbol()))
+        {
+            local.defined = true;
+        }
+    }
+
+    fn declare(&mut self, ident: &SyntaxToken) {
+        let scope = match self.scopes.last_mut() {
+            Some(scope) => scope,
+            None => return,
+        };
+        if let Some(previous) = scope.get(&ident.symbol()) {
+            let error =
+                SyntaxError::at_token("Already a variable with this name in this scope.", ident)
+                    .with_label(previous.declaration.span(), "previous declaration here");
+            self.errors.push(error);
+        }
+        scope.insert(
+            ident.symbol(),
+            Local {
+                declaration: ident.clone(),
+                defined: false,
+            },
+        );
+    }
+
+    fn identifier(&mut self, syntax_node: &SyntaxNode) {
+        let token = ast::Identifier::cast(syntax_node.clone()).unwrap().token();
+        let local = self
+            .scopes
+            .last()
+            .and_then(|scope| scope.get(&token.symbol()));
+        if let Some(local) = local.filter(|local| !local.defined) {
+            let error =
+                SyntaxError::at_token("Can't read local variable in its own initializer.", &token)
+                    .with_label(local.declaration.span(), "variable declared here");
+            self.errors.push(error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Resolver;
+    use crate::{Parser, Scanner};
+
+    /// Formats each error as `offset message`, followed by its labels.
+    fn check_resolve(source: &str, expected: &[&str]) {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan().cloned().collect();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let errors = Resolver::new()
+            .resolve(&statements)
+            .err()
+            .unwrap_or_default();
+        let errors = errors
+            .iter()
+            .map(|error| {
+                let labels = error
+                    .labels()
+                    .iter()
+                    .map(|(span, message)| format!(" ({} {})", span.start, message));
+                format!("{} {}{}", error.offset(), error, labels.collect::<String>())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(errors, expected);
+    }
+
+    #[test]
+    fn local_variables() {
+        check_resolve("var a = a; { var b = 1; { var c = b; } }", &[]);
+        check_resolve(
+            "{ var a = a; }",
+            &["10 Error at 'a': Can't read local variable in its own initializer. (6 variable declared here)"],
+        );
+        check_resolve(
+            "{ var a; a = 1; var a = 2; }",
+            &["20 Error at 'a': Already a variable with this name in this scope. (6 previous declaration here)"],
+        );
+    }
+}
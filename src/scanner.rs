@@ -1,22 +1,37 @@
+use crate::error::SyntaxError;
 use crate::green::SyntaxToken;
 use crate::kinds::SyntaxKind;
 
 macro_rules! is_digit {
     ($c: expr) => {
-        $c >= '0' && $c <= '9'
+        $c.is_ascii_digit()
     };
 }
 
-macro_rules! is_alpha {
-    ($c: expr) => {
-        ($c >= 'a' && $c <= 'z') || ($c >= 'A' && $c <= 'Z') || $c == '_'
-    };
+/// Identifiers follow Unicode's XID rules, like Rust's, with `_` allowed as
+/// a first character too.
+fn is_identifier_start(c: char) -> bool {
+    c == '_' || unicode_ident::is_xid_start(c)
+}
+
+fn is_identifier_continue(c: char) -> bool {
+    unicode_ident::is_xid_continue(c)
 }
 
+/// Splits source into tokens. Offsets are in bytes, and every character is
+/// read once, so scanning takes linear time.
 pub struct Scanner {
     source: String,
     tokens: Vec<SyntaxToken>,
+    comments: Vec<SyntaxToken>,
+    errors: Vec<SyntaxError>,
+    /// How many braces are open inside each `${` interpolation being
+    /// scanned, innermost last. The `}` that closes the interpolation
+    /// carries on with the rest of its string.
+    interpolations: Vec<usize>,
+    /// Where the token being scanned starts.
     start: usize,
+    /// Where the next character starts.
     current: usize,
 }
 
@@ -25,20 +40,45 @@ impl Scanner {
         Scanner {
             source: source.to_string(),
             tokens: Vec::new(),
+            comments: Vec::new(),
+            errors: Vec::new(),
+            interpolations: Vec::new(),
             start: 0,
             current: 0,
         }
     }
 
     pub fn scan(&mut self) -> impl Iterator<Item = &SyntaxToken> {
-        while let Some(c) = self.advance() {
-            self.start = self.current - 1;
+        loop {
+            self.start = self.current;
+            let c = match self.advance() {
+                Some(c) => c,
+                None => break,
+            };
             match c {
                 '(' => self.add_token(SyntaxKind::LeftParen),
                 ')' => self.add_token(SyntaxKind::RightParen),
-                '{' => self.add_token(SyntaxKind::LeftBrace),
-                '}' => self.add_token(SyntaxKind::RightBrace),
+                '{' => {
+                    if let Some(depth) = self.interpolations.last_mut() {
+                        *depth += 1;
+                    }
+                    self.add_token(SyntaxKind::LeftBrace)
+                }
+                '}' => match self.interpolations.last_mut() {
+                    Some(0) => {
+                        self.interpolations.pop();
+                        self.string(SyntaxKind::StringMiddle, SyntaxKind::StringTail)
+                    }
+                    Some(depth) => {
+                        *depth -= 1;
+                        self.add_token(SyntaxKind::RightBrace)
+                    }
+                    None => self.add_token(SyntaxKind::RightBrace),
+                },
+                '[' => self.add_token(SyntaxKind::LeftBracket),
+                ']' => self.add_token(SyntaxKind::RightBracket),
                 ',' => self.add_token(SyntaxKind::Comma),
+                ':' => self.add_token(SyntaxKind::Colon),
                 '.' => self.add_token(SyntaxKind::Dot),
                 '-' => self.add_token(SyntaxKind::Minus),
                 '+' => self.add_token(SyntaxKind::Plus),
@@ -73,31 +113,57 @@ impl Scanner {
 
                 ' ' | '\r' | '\t' | '\n' => {}
 
-                '"' => self.string(),
+                '"' => self.string(SyntaxKind::StringHead, SyntaxKind::String),
 
                 '0'..='9' => self.number(),
 
-                'a'..='z' | 'A'..='Z' | '_' => self.identifier(),
+                c if is_identifier_start(c) => self.identifier(),
 
-                _ => panic!("Unexpected character: {}.", c),
+                _ => self.error("Unexpected character."),
             }
         }
         self.tokens.iter()
     }
 
+    /// The comments skipped by [`scan`](Scanner::scan): `//` comments without
+    /// their line breaks, `///` doc comments, and `/* */` comments. The parser
+    /// never sees them, except for doc comments handed to
+    /// [`Parser::with_docs`](crate::Parser::with_docs); tools like the
+    /// formatter use their offsets to put them back.
+    pub fn comments(&self) -> &[SyntaxToken] {
+        &self.comments
+    }
+
+    /// Errors found by [`scan`](Scanner::scan). Offending characters are
+    /// skipped, so the tokens around them are still produced.
+    pub fn errors(&self) -> &[SyntaxError] {
+        &self.errors
+    }
+
+    fn error(&mut self, message: &str) {
+        self.errors
+            .push(SyntaxError::new(message, self.start..self.current));
+    }
+
     fn peek(&self) -> Option<char> {
-        self.source.chars().nth(self.current)
+        self.source[self.current..].chars().next()
+    }
+
+    fn peek_next(&self) -> Option<char> {
+        let mut chars = self.source[self.current..].chars();
+        chars.next();
+        chars.next()
     }
 
     fn advance(&mut self) -> Option<char> {
-        let char = self.peek();
-        self.current += 1;
-        char
+        let c = self.peek()?;
+        self.current += c.len_utf8();
+        Some(c)
     }
 
     fn add_token(&mut self, kind: SyntaxKind) {
         let text = &self.source[self.start..self.current];
-        let token = SyntaxToken::new(kind, text.to_string());
+        let token = SyntaxToken::new(kind, text, self.start);
         self.tokens.push(token);
     }
 
@@ -109,7 +175,7 @@ impl Scanner {
     ) {
         if let Some(c) = self.peek() {
             if c == expected {
-                self.current += 1;
+                self.advance();
                 self.add_token(expected_syntax_kind);
             } else {
                 self.add_token(otherwise_syntax_kind);
@@ -120,64 +186,204 @@ impl Scanner {
     }
 
     fn slash(&mut self) {
-        if let Some(current) = self.peek() {
-            if current == '/' {
-                while let Some(next) = self.advance() {
+        match self.peek() {
+            Some('/') => {
+                while let Some(next) = self.peek() {
                     if next == '\n' {
                         break;
                     }
+                    self.advance();
                 }
-            } else {
-                self.add_token(SyntaxKind::Slash);
+                let text = &self.source[self.start..self.current];
+                let kind = if text.starts_with("///") && !text.starts_with("////") {
+                    SyntaxKind::DocComment
+                } else {
+                    SyntaxKind::Comment
+                };
+                let comment = SyntaxToken::new(kind, text, self.start);
+                self.comments.push(comment);
             }
-        } else {
-            self.add_token(SyntaxKind::Slash);
+            Some('*') => self.block_comment(),
+            _ => self.add_token(SyntaxKind::Slash),
         }
     }
 
-    fn string(&mut self) {
+    /// Scans a `/* */` comment, which may contain nested ones.
+    fn block_comment(&mut self) {
+        self.advance();
+        let mut depth = 1;
+        while depth > 0 {
+            match self.advance() {
+                Some('/') if self.peek() == Some('*') => {
+                    self.advance();
+                    depth += 1;
+                }
+                Some('*') if self.peek() == Some('/') => {
+                    self.advance();
+                    depth -= 1;
+                }
+                Some(_) => {}
+                None => return self.error("Unterminated comment."),
+            }
+        }
+        let text = &self.source[self.start..self.current];
+        let comment = SyntaxToken::new(SyntaxKind::Comment, text, self.start);
+        self.comments.push(comment);
+    }
+
+    /// Scans the rest of a string, or of the piece of one after an
+    /// interpolation. It ends with a `closed` token at the closing quote, or
+    /// an `interpolated` one at the next `${`.
+    fn string(&mut self, interpolated: SyntaxKind, closed: SyntaxKind) {
         while let Some(c) = self.advance() {
-            if c == '"' {
-                self.add_token(SyntaxKind::String);
-                return;
+            match c {
+                '"' => return self.add_token(closed),
+                '\\' => self.escape(),
+                '$' if self.peek() == Some('{') => {
+                    self.advance();
+                    self.interpolations.push(0);
+                    return self.add_token(interpolated);
+                }
+                _ => {}
             }
         }
-        panic!("Unterminated string.")
+        self.error("Unterminated string.")
     }
 
+    /// Checks the escape sequence after a `\` in a string.
+    fn escape(&mut self) {
+        let start = self.current - '\\'.len_utf8();
+        let message = match self.advance() {
+            Some('n' | 't' | 'r' | '"' | '\\' | '$') | None => return,
+            Some('u') => {
+                let mut digits = String::new();
+                let braced = self.peek() == Some('{');
+                if braced {
+                    self.advance();
+                    while let Some(c) = self.peek().filter(char::is_ascii_hexdigit) {
+                        digits.push(c);
+                        self.advance();
+                    }
+                }
+                let closed = braced && self.peek() == Some('}');
+                if closed {
+                    self.advance();
+                }
+                let code_point = u32::from_str_radix(&digits, 16).ok();
+                if closed && digits.len() <= 6 && code_point.and_then(char::from_u32).is_some() {
+                    return;
+                }
+                "Invalid Unicode escape sequence.".to_string()
+            }
+            Some(c) => format!("Invalid escape sequence '\\{}'.", c),
+        };
+        self.errors
+            .push(SyntaxError::new(message, start..self.current));
+    }
+
+    /// Scans a decimal literal like `1_000.5e-3`, or a `0x` hexadecimal or
+    /// `0b` binary integer. A `.` is only part of the literal if a digit
+    /// follows it, so `1.abs()` is a property of `1`. A malformed literal is
+    /// reported and still becomes a token.
     fn number(&mut self) {
-        while let Some(c) = self.advance() {
-            if !is_digit!(c) && c != '.' {
+        let first = &self.source[self.start..self.current];
+        let radix = match (first, self.peek()) {
+            ("0", Some('x' | 'X')) => 16,
+            ("0", Some('b' | 'B')) => 2,
+            _ => 10,
+        };
+        let mut error = None;
+        if radix == 10 {
+            self.digits(10, true, &mut error);
+            if self.peek() == Some('.') && self.peek_next().is_some_and(|c| is_digit!(c)) {
+                self.advance();
+                self.digits(10, false, &mut error);
+            }
+            if let Some('e' | 'E') = self.peek() {
+                self.advance();
+                if let Some('+' | '-') = self.peek() {
+                    self.advance();
+                }
+                if !self.digits(10, false, &mut error) {
+                    error.get_or_insert("Expect digits in exponent.".to_string());
+                }
+            }
+        } else {
+            let prefix = self.advance().unwrap();
+            if !self.digits(radix, false, &mut error) {
+                error.get_or_insert(format!("Expect digits after '0{}'.", prefix));
+            }
+        }
+
+        // Whatever runs on from the literal, like the `G` in `0x1G` or the
+        // `.3` in `1.2.3`, is part of the malformed literal.
+        let mut trailing = false;
+        while let Some(c) = self.peek() {
+            let digit_after_dot = c == '.' && self.peek_next().is_some_and(|c| is_digit!(c));
+            if !is_identifier_continue(c) && !digit_after_dot {
                 break;
             }
+            self.advance();
+            trailing = true;
+        }
+        if trailing {
+            error.get_or_insert("Invalid number literal.".to_string());
+        }
+        if let Some(message) = error {
+            self.error(&message);
         }
-        self.current -= 1;
         self.add_token(SyntaxKind::Number);
     }
 
-    fn identifier(&mut self) {
-        while let Some(c) = self.advance() {
-            if !is_alpha!(c) {
-                break;
+    /// Consumes digits in `radix`, and `_` separators between them. Returns
+    /// whether there were any digits, counting the one `number` started
+    /// with if `started`.
+    fn digits(&mut self, radix: u32, started: bool, error: &mut Option<String>) -> bool {
+        let mut any = started;
+        loop {
+            match self.peek() {
+                Some(c) if c.is_digit(radix) => any = true,
+                Some('_') => {
+                    let between = any && self.peek_next().is_some_and(|c| c.is_digit(radix));
+                    if !between {
+                        error.get_or_insert("Digit separators must be between digits.".to_string());
+                    }
+                }
+                _ => return any,
             }
+            self.advance();
+        }
+    }
+
+    fn identifier(&mut self) {
+        while self.peek().is_some_and(is_identifier_continue) {
+            self.advance();
         }
-        self.current -= 1;
         let text = &self.source[self.start..self.current];
         match text {
             "and" => self.add_token(SyntaxKind::And),
+            "as" => self.add_token(SyntaxKind::As),
+            "break" => self.add_token(SyntaxKind::Break),
+            "catch" => self.add_token(SyntaxKind::Catch),
             "class" => self.add_token(SyntaxKind::Class),
+            "continue" => self.add_token(SyntaxKind::Continue),
             "else" => self.add_token(SyntaxKind::Else),
+            "export" => self.add_token(SyntaxKind::Export),
             "false" => self.add_token(SyntaxKind::False),
+            "finally" => self.add_token(SyntaxKind::Finally),
             "for" => self.add_token(SyntaxKind::For),
             "fun" => self.add_token(SyntaxKind::Fun),
             "if" => self.add_token(SyntaxKind::If),
+            "import" => self.add_token(SyntaxKind::Import),
             "nil" => self.add_token(SyntaxKind::Nil),
             "or" => self.add_token(SyntaxKind::Or),
             "print" => self.add_token(SyntaxKind::Print),
             "return" => self.add_token(SyntaxKind::Return),
             "super" => self.add_token(SyntaxKind::Super),
             "this" => self.add_token(SyntaxKind::This),
+            "throw" => self.add_token(SyntaxKind::Throw),
             "true" => self.add_token(SyntaxKind::True),
+            "try" => self.add_token(SyntaxKind::Try),
             "var" => self.add_token(SyntaxKind::Var),
             "while" => self.add_token(SyntaxKind::While),
             _ => self.add_token(SyntaxKind::Identifier),
@@ -185,9 +391,98 @@ impl Scanner {
     }
 }
 
+/// The value of a `Number` token. The scanner has reported malformed
+/// literals, which are read as best they can be, or as NaN.
+pub(crate) fn number_value(token: &SyntaxToken) -> f64 {
+    let text = token.text().replace('_', "");
+    let radix = match text.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0b" | "0B") => 2,
+        _ => return text.parse().unwrap_or(f64::NAN),
+    };
+    text[2..]
+        .chars()
+        .try_fold(0., |value, c| {
+            Some(value * f64::from(radix) + f64::from(c.to_digit(radix)?))
+        })
+        .unwrap_or(f64::NAN)
+}
+
+/// The value of a `String` token or of a piece of an interpolated string,
+/// without its delimiters and with its escape sequences replaced.
+pub(crate) fn string_value(token: &SyntaxToken) -> String {
+    let text = token.text();
+    let end = match token.kind() {
+        SyntaxKind::StringHead | SyntaxKind::StringMiddle => text.len() - "${".len(),
+        _ => text.len() - "\"".len(),
+    };
+    unescape(&text[1..end])
+}
+
+/// Replaces the escape sequences in the contents of a string. The scanner
+/// has reported invalid ones, which are left as they are.
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let rest = chars.as_str();
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some(c @ ('"' | '\\' | '$')) => out.push(c),
+            Some('u') => {
+                let escape = rest[1..]
+                    .strip_prefix('{')
+                    .and_then(|braced| braced.split_once('}'));
+                let code_point = escape
+                    .and_then(|(digits, _)| u32::from_str_radix(digits, 16).ok())
+                    .and_then(char::from_u32);
+                match (code_point, escape) {
+                    (Some(c), Some((_, after))) => {
+                        out.push(c);
+                        chars = after.chars();
+                    }
+                    _ => out.push_str("\\u"),
+                }
+            }
+            Some(c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// A string literal whose value is `text`.
+pub(crate) fn quote(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => out += "\\\"",
+            '\\' => out += "\\\\",
+            '\n' => out += "\\n",
+            '\t' => out += "\\t",
+            '\r' => out += "\\r",
+            '$' if chars.peek() == Some(&'{') => out += "\\$",
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Scanner, SyntaxToken};
+    use super::{quote, string_value, Scanner, SyntaxToken};
     use crate::kinds::SyntaxKind;
 
     fn test_scan_one_token(source: &str, kind: SyntaxKind) {
@@ -246,6 +541,37 @@ mod tests {
         test_scan_expected_empty("//");
         test_scan_expected_empty("//asdfasdf");
         test_scan_one_token_with_text("//asdfasdf\n/", SyntaxKind::Slash, "/");
+        test_scan_one_token_with_text("/* a\n/* b */ */ /", SyntaxKind::Slash, "/");
+    }
+
+    #[test]
+    fn comments() {
+        let mut scanner = Scanner::new("/// a\n//// b\n/* c /* d */ */ 1 /**/ // e");
+        assert_eq!(scanner.scan().count(), 1);
+        let comments = scanner
+            .comments()
+            .iter()
+            .map(|comment| (comment.kind(), comment.text()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            comments,
+            vec![
+                (SyntaxKind::DocComment, "/// a"),
+                (SyntaxKind::Comment, "//// b"),
+                (SyntaxKind::Comment, "/* c /* d */ */"),
+                (SyntaxKind::Comment, "/**/"),
+                (SyntaxKind::Comment, "// e"),
+            ]
+        );
+
+        let mut scanner = Scanner::new("1 /* a /* b */");
+        assert_eq!(scanner.scan().count(), 1);
+        let errors = scanner
+            .errors()
+            .iter()
+            .map(|error| (error.message(), error.span()))
+            .collect::<Vec<_>>();
+        assert_eq!(errors, vec![("Unterminated comment.", 2..14)]);
     }
 
     #[test]
@@ -268,17 +594,138 @@ mod tests {
         test_scan_one_token("\" \"", SyntaxKind::String);
     }
 
+    #[test]
+    fn interpolation() {
+        let mut scanner = Scanner::new(r#""a ${ {"b": "${c}"} } d" }"#);
+        let tokens = scanner
+            .scan()
+            .map(|token| format!("{:?} {}", token.kind(), token.text()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                "StringHead \"a ${",
+                "LeftBrace {",
+                "String \"b\"",
+                "Colon :",
+                "StringHead \"${",
+                "Identifier c",
+                "StringTail }\"",
+                "RightBrace }",
+                "StringTail } d\"",
+                "RightBrace }",
+            ]
+        );
+        assert!(scanner.errors().is_empty());
+    }
+
+    #[test]
+    fn escapes() {
+        let mut scanner = Scanner::new(r#""\n\t\"\\\$\u{1F600}" "\a\u{}\u{FFFFFFF} \u""#);
+        let tokens = scanner.scan().cloned().collect::<Vec<_>>();
+        assert_eq!(string_value(&tokens[0]), "\n\t\"\\$\u{1F600}");
+        let errors = scanner
+            .errors()
+            .iter()
+            .map(|error| (error.message(), error.span()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                ("Invalid escape sequence '\\a'.", 23..25),
+                ("Invalid Unicode escape sequence.", 25..29),
+                ("Invalid Unicode escape sequence.", 29..40),
+                ("Invalid Unicode escape sequence.", 41..43),
+            ]
+        );
+        // Invalid escapes are kept as written.
+        assert_eq!(string_value(&tokens[1]), "\\a\\u{}\\u{FFFFFFF} \\u");
+
+        let text = "\"quoted\" ${x} $5 \\ \n";
+        let literal = quote(text);
+        assert_eq!(literal, r#""\"quoted\" \${x} $5 \\ \n""#);
+        let mut scanner = Scanner::new(&literal);
+        assert_eq!(string_value(scanner.scan().next().unwrap()), text);
+    }
+
     #[test]
     fn number() {
-        test_scan_one_token("123", SyntaxKind::Number);
-        // TODO: should panic?
-        test_scan_one_token("0123", SyntaxKind::Number);
-        test_scan_one_token("123.", SyntaxKind::Number);
-        test_scan_one_token("123.43", SyntaxKind::Number);
-        // TODO: should panic?
-        test_scan_one_token("123..43", SyntaxKind::Number);
-        // TODO: should panic?
-        test_scan_one_token("123.4.3", SyntaxKind::Number);
+        let value = |source: &str| {
+            let mut scanner = Scanner::new(source);
+            let tokens = scanner.scan().cloned().collect::<Vec<_>>();
+            assert_eq!(tokens.len(), 1, "{}", source);
+            assert!(scanner.errors().is_empty(), "{}", source);
+            super::number_value(&tokens[0])
+        };
+        assert_eq!(value("123"), 123.);
+        assert_eq!(value("0123"), 123.);
+        assert_eq!(value("123.43"), 123.43);
+        assert_eq!(value("1_000_000"), 1_000_000.);
+        assert_eq!(value("0x1F"), 31.);
+        assert_eq!(value("0Xff_ff"), 65535.);
+        assert_eq!(value("0b1010"), 10.);
+        assert_eq!(value("1e-9"), 1e-9);
+        assert_eq!(value("2.5E+3"), 2500.);
+
+        // A dot without a digit after it is a property access.
+        let mut scanner = Scanner::new("1.abs() 123. 1..2");
+        let tokens = scanner.scan().map(|token| token.text()).collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec!["1", ".", "abs", "(", ")", "123", ".", "1", ".", ".", "2"]
+        );
+    }
+
+    #[test]
+    fn malformed_numbers() {
+        let check = |source: &str, message: &str| {
+            let mut scanner = Scanner::new(source);
+            let tokens = scanner.scan().cloned().collect::<Vec<_>>();
+            assert_eq!(tokens.len(), 1, "{}", source);
+            assert_eq!(tokens[0].text(), source);
+            let errors = scanner
+                .errors()
+                .iter()
+                .map(|error| (error.message(), error.offset()))
+                .collect::<Vec<_>>();
+            assert_eq!(errors, vec![(message, 0)], "{}", source);
+        };
+        check("1.2.3", "Invalid number literal.");
+        check("0x1G", "Invalid number literal.");
+        check("0b102", "Invalid number literal.");
+        check("12ab", "Invalid number literal.");
+        check("0x", "Expect digits after '0x'.");
+        check("0b_1", "Digit separators must be between digits.");
+        check("1__0", "Digit separators must be between digits.");
+        check("1_", "Digit separators must be between digits.");
+        check("1e", "Expect digits in exponent.");
+        check("1e+", "Expect digits in exponent.");
+    }
+
+    #[test]
+    fn errors() {
+        let mut scanner = Scanner::new("a\n@ \"b");
+        let kinds = scanner.scan().map(|token| token.kind()).collect::<Vec<_>>();
+        assert_eq!(kinds, vec![SyntaxKind::Identifier]);
+        let errors = scanner
+            .errors()
+            .iter()
+            .map(|error| (error.message(), error.offset()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![("Unexpected character.", 2), ("Unterminated string.", 4)]
+        );
+    }
+
+    #[test]
+    fn offsets() {
+        let mut scanner = Scanner::new("var ab = \"c\";");
+        let offsets = scanner
+            .scan()
+            .map(|token| token.offset())
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![0, 4, 7, 9, 12]);
     }
 
     #[test]
@@ -307,5 +754,43 @@ mod tests {
         test_scan_one_token("_key", SyntaxKind::Identifier);
         test_scan_one_token("__key", SyntaxKind::Identifier);
         test_scan_one_token("k_e_y", SyntaxKind::Identifier);
+        test_scan_one_token("key2", SyntaxKind::Identifier);
+        test_scan_one_token("_1", SyntaxKind::Identifier);
+        test_scan_one_token("naïve", SyntaxKind::Identifier);
+        test_scan_one_token("变量", SyntaxKind::Identifier);
+        test_scan_one_token("Δx", SyntaxKind::Identifier);
+    }
+
+    #[test]
+    fn unicode() {
+        let source = "var café = \"☕\"; // ☕☕\n€ 🦀";
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner
+            .scan()
+            .map(|token| (token.text().to_string(), token.span()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                ("var".to_string(), 0..3),
+                ("café".to_string(), 4..9),
+                ("=".to_string(), 10..11),
+                ("\"☕\"".to_string(), 12..17),
+                (";".to_string(), 17..18),
+            ]
+        );
+        assert_eq!(scanner.comments()[0].text(), "// ☕☕");
+        let errors = scanner
+            .errors()
+            .iter()
+            .map(|error| (error.message(), error.span()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                ("Unexpected character.", 29..32),
+                ("Unexpected character.", 33..37)
+            ]
+        );
     }
 }
@@ -0,0 +1,288 @@
+use crate::ast::{self, AstNode};
+use crate::green::{SyntaxElement, SyntaxNode, SyntaxToken};
+use crate::kinds::SyntaxKind;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// How a variable is declared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeclarationKind {
+    Var,
+    Import,
+    /// The error variable of a `catch` clause.
+    Catch,
+}
+
+/// A variable declared with `var`, or bound by an `import` or a `catch`
+/// clause.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub name: String,
+    pub kind: DeclarationKind,
+    /// The name in the declaration.
+    pub name_span: Range<usize>,
+    /// For a `var`, the name and the initializer, if there is one; for an
+    /// import, the statement; for a catch variable, the name.
+    pub span: Range<usize>,
+    /// Index of the scope the variable is declared in.
+    pub scope: usize,
+    /// The declaration's `///` comments, if the parser attached them.
+    pub doc: Option<String>,
+}
+
+/// A use of a variable by name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub span: Range<usize>,
+    /// Index of the declaration the name resolves to, or `None` for names
+    /// the program never declares, like natives.
+    pub declaration: Option<usize>,
+    /// Whether the variable is assigned rather than read.
+    pub write: bool,
+    /// Index of the scope the reference is in.
+    pub scope: usize,
+}
+
+/// A block, or the global scope at index 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scope {
+    pub parent: Option<usize>,
+    pub span: Range<usize>,
+}
+
+/// Every variable declaration in a program and the names that refer to it,
+/// resolved the way the interpreter looks variables up.
+#[derive(Debug)]
+pub struct SymbolTable {
+    declarations: Vec<Declaration>,
+    references: Vec<Reference>,
+    scopes: Vec<Scope>,
+}
+
+impl SymbolTable {
+    pub fn new(statements: &[SyntaxNode]) -> SymbolTable {
+        let mut builder = Builder {
+            table: SymbolTable {
+                declarations: Vec::new(),
+                references: Vec::new(),
+                scopes: vec![Scope {
+                    parent: None,
+                    span: 0..usize::MAX,
+                }],
+            },
+            visible: vec![(0, HashMap::new())],
+        };
+        for statement in statements {
+            builder.node(statement);
+        }
+        builder.table
+    }
+
+    pub fn declarations(&self) -> &[Declaration] {
+        &self.declarations
+    }
+
+    pub fn references(&self) -> &[Reference] {
+        &self.references
+    }
+
+    pub fn scopes(&self) -> &[Scope] {
+        &self.scopes
+    }
+
+    /// The declaration whose name, or a reference to it, covers `offset`.
+    pub fn declaration_at(&self, offset: usize) -> Option<usize> {
+        let covers = |span: &Range<usize>| span.start <= offset && offset <= span.end;
+        self.declarations
+            .iter()
+            .position(|declaration| covers(&declaration.name_span))
+            .or_else(|| {
+                self.references
+                    .iter()
+                    .find(|reference| covers(&reference.span))?
+                    .declaration
+            })
+    }
+
+    pub fn references_to(&self, declaration: usize) -> impl Iterator<Item = &Reference> {
+        self.references
+            .iter()
+            .filter(move |reference| reference.declaration == Some(declaration))
+    }
+
+    /// The declaration `name` would resolve to if it were used at `offset`
+    /// inside `scope`.
+    pub fn lookup(&self, name: &str, scope: usize, offset: usize) -> Option<usize> {
+        let mut scope = Some(scope);
+        while let Some(index) = scope {
+            let found = self
+                .declarations
+                .iter()
+                .enumerate()
+                .filter(|(_, declaration)| {
+                    declaration.scope == index
+                        && declaration.name == name
+                        && declaration.name_span.end <= offset
+                })
+                .max_by_key(|(_, declaration)| declaration.name_span.start);
+            if let Some((found, _)) = found {
+                return Some(found);
+            }
+            scope = self.scopes[index].parent;
+        }
+        None
+    }
+}
+
+struct Builder {
+    table: SymbolTable,
+    /// The scopes enclosing the current node, with the variables declared
+    /// in them so far.
+    visible: Vec<(usize, HashMap<String, usize>)>,
+}
+
+impl Builder {
+    fn node(&mut self, syntax_node: &SyntaxNode) {
+        match syntax_node.kind() {
+            SyntaxKind::Block | SyntaxKind::For => {
+                let scope = self.table.scopes.len();
+                self.table.scopes.push(Scope {
+                    parent: Some(self.scope()),
+                    span: syntax_node.span().unwrap_or(0..0),
+                });
+                self.visible.push((scope, HashMap::new()));
+                self.children(syntax_node);
+                self.visible.pop();
+            }
+            SyntaxKind::Catch => {
+                let catch = ast::Catch::cast(syntax_node.clone()).unwrap();
+                let scope = self.table.scopes.len();
+                self.table.scopes.push(Scope {
+                    parent: Some(self.scope()),
+                    span: syntax_node.span().unwrap_or(0..0),
+                });
+                self.visible.push((scope, HashMap::new()));
+                let name = catch.name();
+                let span = name.span();
+                self.declare(name, DeclarationKind::Catch, span);
+                self.node(&catch.body());
+                self.visible.pop();
+            }
+            SyntaxKind::Import => {
+                let name = ast::Import::cast(syntax_node.clone()).unwrap().name();
+                let span = syntax_node.span().unwrap_or_else(|| name.span());
+                self.declare(name, DeclarationKind::Import, span);
+            }
+            SyntaxKind::Var => {
+                let var_declaration = ast::VarDeclaration::cast(syntax_node.clone()).unwrap();
+                self.node(&var_declaration.initializer());
+                let ident = var_declaration.ident();
+                let end = syntax_node.span().map_or(ident.span().end, |span| span.end);
+                self.declare(ident.clone(), DeclarationKind::Var, ident.offset()..end);
+                let declaration = self.table.declarations.last_mut().unwrap();
+                declaration.doc = var_declaration.doc();
+            }
+            SyntaxKind::Identifier => self.reference(syntax_node, false),
+            SyntaxKind::Assign => {
+                let assign = ast::Assign::cast(syntax_node.clone()).unwrap();
+                let target = assign.target();
+                if target.kind() == SyntaxKind::Index {
+                    return self.children(syntax_node);
+                }
+                self.node(&assign.value());
+                self.reference(&target, true);
+            }
+            _ => self.children(syntax_node),
+        }
+    }
+
+    /// Declares `ident` in the current scope, for the declaration at `span`.
+    fn declare(&mut self, ident: SyntaxToken, kind: DeclarationKind, span: Range<usize>) {
+        let declaration = self.table.declarations.len();
+        self.table.declarations.push(Declaration {
+            name: ident.text().to_string(),
+            kind,
+            name_span: ident.span(),
+            span,
+            scope: self.scope(),
+            doc: None,
+        });
+        let (_, names) = self.visible.last_mut().unwrap();
+        names.insert(ident.text().to_string(), declaration);
+    }
+
+    fn children(&mut self, syntax_node: &SyntaxNode) {
+        for child in syntax_node.children().filter_map(SyntaxElement::into_node) {
+            self.node(&child);
+        }
+    }
+
+    fn reference(&mut self, syntax_node: &SyntaxNode, write: bool) {
+        let token = ast::Identifier::cast(syntax_node.clone()).unwrap().token();
+        let declaration = self
+            .visible
+            .iter()
+            .rev()
+            .find_map(|(_, names)| names.get(token.text()).copied());
+        self.table.references.push(Reference {
+            span: token.span(),
+            declaration,
+            write,
+            scope: self.scope(),
+        });
+    }
+
+    fn scope(&self) -> usize {
+        self.visible.last().unwrap().0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SymbolTable;
+    use crate::{Parser, Scanner};
+
+    fn symbols(source: &str) -> SymbolTable {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan().cloned().collect();
+        SymbolTable::new(&Parser::new(tokens).parse().unwrap())
+    }
+
+    #[test]
+    fn resolves_like_the_interpreter() {
+        //           0         1         2         3         4
+        //           0123456789012345678901234567890123456789012345
+        let source = "var a = 1; { var b = a; var a = b; a = a; } a;";
+        let table = symbols(source);
+        let names = table
+            .declarations()
+            .iter()
+            .map(|declaration| (declaration.name.as_str(), declaration.scope))
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![("a", 0), ("b", 1), ("a", 1)]);
+
+        let uses = |declaration| {
+            table
+                .references_to(declaration)
+                .map(|reference| (reference.span.start, reference.write))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(uses(0), vec![(21, false), (44, false)]);
+        assert_eq!(uses(1), vec![(32, false)]);
+        assert_eq!(uses(2), vec![(39, false), (35, true)]);
+
+        assert_eq!(table.declaration_at(4), Some(0));
+        assert_eq!(table.declaration_at(39), Some(2));
+        assert_eq!(table.declaration_at(8), None);
+    }
+
+    #[test]
+    fn lookup() {
+        let table = symbols("var a; { print a; var a; print a; } print clock;");
+        assert_eq!(table.lookup("a", 1, 15), Some(0));
+        assert_eq!(table.lookup("a", 1, 30), Some(1));
+        assert_eq!(table.lookup("a", 0, 40), Some(0));
+        assert_eq!(table.lookup("clock", 0, 40), None);
+        assert_eq!(table.references().last().unwrap().declaration, None);
+    }
+}
@@ -1,20 +1,177 @@
+use crate::map::Map;
+use crate::module::Module;
+use crate::native::NativeFunction;
+use std::cell::RefCell;
 use std::fmt;
+use std::rc::Rc;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Value {
     Bool(bool),
-    String(String),
-    Number(f32),
+    String(Rc<str>),
+    Number(f64),
+    NativeFunction(Rc<NativeFunction>),
+    /// Copies of a list share its elements, so changes made through one are
+    /// seen through all of them.
+    List(Rc<RefCell<Vec<Value>>>),
+    /// Shared like a list.
+    Map(Rc<RefCell<Map>>),
+    /// The namespace an `import` binds, holding what the module exported.
+    Module(Rc<Module>),
     Nil,
 }
 
+impl Value {
+    /// The name of the value's type, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Bool(_) => "boolean",
+            Value::String(_) => "string",
+            Value::Number(_) => "number",
+            Value::NativeFunction(_) => "function",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+            Value::Module(_) => "module",
+            Value::Nil => "nil",
+        }
+    }
+
+    pub fn list(elements: Vec<Value>) -> Value {
+        Value::List(Rc::new(RefCell::new(elements)))
+    }
+
+    pub fn map(map: Map) -> Value {
+        Value::Map(Rc::new(RefCell::new(map)))
+    }
+}
+
+/// Lists, maps and modules are compared by identity, like natives: two lists are equal
+/// only if they are the same list, not merely lists with equal elements.
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Bool(left), Value::Bool(right)) => left == right,
+            (Value::String(left), Value::String(right)) => left == right,
+            (Value::Number(left), Value::Number(right)) => left == right,
+            (Value::NativeFunction(left), Value::NativeFunction(right)) => left == right,
+            (Value::List(left), Value::List(right)) => Rc::ptr_eq(left, right),
+            (Value::Map(left), Value::Map(right)) => Rc::ptr_eq(left, right),
+            (Value::Module(left), Value::Module(right)) => Rc::ptr_eq(left, right),
+            (Value::Nil, Value::Nil) => true,
+            _ => false,
+        }
+    }
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Value::Bool(b) => fmt::Display::fmt(b, f),
             Value::String(s) => fmt::Display::fmt(s, f),
-            Value::Number(n) => fmt::Display::fmt(n, f),
+            Value::Number(n) => fmt_number(*n, f),
+            Value::NativeFunction(function) => fmt::Display::fmt(function, f),
+            Value::List(_) | Value::Map(_) => fmt_collection(self, f, &mut Vec::new()),
+            Value::Module(module) => write!(f, "<module {}>", module.path()),
             Value::Nil => fmt::Display::fmt("nil", f),
         }
     }
 }
+
+/// Prints the elements of a list or map, or `[...]` or `{...}` for one that
+/// contains itself and is already being printed further up in `open`.
+fn fmt_collection(
+    value: &Value,
+    f: &mut fmt::Formatter<'_>,
+    open: &mut Vec<*const ()>,
+) -> fmt::Result {
+    let (pointer, brackets) = match value {
+        Value::List(list) => (Rc::as_ptr(list) as *const (), ("[", "]")),
+        Value::Map(map) => (Rc::as_ptr(map) as *const (), ("{", "}")),
+        value => return fmt::Display::fmt(value, f),
+    };
+    if open.contains(&pointer) {
+        return write!(f, "{}...{}", brackets.0, brackets.1);
+    }
+    open.push(pointer);
+    f.write_str(brackets.0)?;
+    match value {
+        Value::List(list) => {
+            for (index, element) in list.borrow().iter().enumerate() {
+                if index > 0 {
+                    f.write_str(", ")?;
+                }
+                fmt_collection(element, f, open)?;
+            }
+        }
+        Value::Map(map) => {
+            for (index, (key, value)) in map.borrow().iter().enumerate() {
+                if index > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}: ", key.to_value())?;
+                fmt_collection(value, f, open)?;
+            }
+        }
+        _ => unreachable!(),
+    }
+    open.pop();
+    f.write_str(brackets.1)
+}
+
+/// Integral numbers print without a fractional part (`3`, not `3.0`) and the
+/// non-finite values use the same spelling as the reference Lox implementation.
+fn fmt_number(n: f64, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if n.is_nan() {
+        fmt::Display::fmt("NaN", f)
+    } else if n.is_infinite() {
+        let text = if n > 0. { "Infinity" } else { "-Infinity" };
+        fmt::Display::fmt(text, f)
+    } else {
+        fmt::Display::fmt(&n, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Value;
+
+    #[test]
+    fn number_display() {
+        assert_eq!(Value::Number(3.).to_string(), "3");
+        assert_eq!(Value::Number(-0.).to_string(), "-0");
+        assert_eq!(Value::Number(2.5).to_string(), "2.5");
+        assert_eq!(Value::Number(16777217.).to_string(), "16777217");
+        assert_eq!(
+            Value::Number(9007199254740992.).to_string(),
+            "9007199254740992"
+        );
+        assert_eq!(Value::Number(0.1 + 0.2).to_string(), "0.30000000000000004");
+        assert_eq!(Value::Number(f64::NAN).to_string(), "NaN");
+        assert_eq!(Value::Number(f64::INFINITY).to_string(), "Infinity");
+        assert_eq!(Value::Number(f64::NEG_INFINITY).to_string(), "-Infinity");
+    }
+
+    #[test]
+    fn lists() {
+        let list = Value::list(vec![
+            Value::Number(1.),
+            Value::String("a".into()),
+            Value::Nil,
+        ]);
+        assert_eq!(list.to_string(), "[1, a, nil]");
+        assert_eq!(list, list.clone());
+        assert_ne!(
+            list,
+            Value::list(vec![
+                Value::Number(1.),
+                Value::String("a".into()),
+                Value::Nil
+            ])
+        );
+
+        if let Value::List(elements) = &list {
+            elements.borrow_mut().push(list.clone());
+        }
+        assert_eq!(list.to_string(), "[1, a, nil, [...]]");
+    }
+}
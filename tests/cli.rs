@@ -0,0 +1,235 @@
+use std::fs;
+use std::process::Command;
+
+fn rlox(name: &str, source: &str) -> (Option<i32>, String) {
+    rlox_with(&[], name, source)
+}
+
+/// Runs `rlox <args> <script>` and returns its exit code and stderr.
+fn rlox_with(args: &[&str], name: &str, source: &str) -> (Option<i32>, String) {
+    let path = std::env::temp_dir().join(format!("rlox-cli-{}.lox", name));
+    fs::write(&path, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rlox"))
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let stderr = stderr.replace(&path.display().to_string(), "test.lox");
+    (output.status.code(), stderr)
+}
+
+#[test]
+fn runtime_errors() {
+    let (code, stderr) = rlox("runtime", "print 1;\nclock(\n  nil);\n");
+    assert_eq!(code, Some(70));
+    assert_eq!(
+        stderr,
+        "\
+error: Expected 0 arguments but got 1.
+ --> test.lox:3:6
+  |
+3 |   nil);
+  |      ^
+"
+    );
+}
+
+#[test]
+fn syntax_errors() {
+    let (code, stderr) = rlox("syntax", "print 1\n");
+    assert_eq!(code, Some(65));
+    assert_eq!(
+        stderr,
+        "\
+error: Expect ';' after value.
+ --> test.lox:1:8
+  |
+1 | print 1
+  |        ^
+"
+    );
+}
+
+#[test]
+fn resolver_errors() {
+    let (code, stderr) = rlox("resolver", "{\n  var a = a;\n}\n");
+    assert_eq!(code, Some(65));
+    assert_eq!(
+        stderr,
+        "\
+error: Can't read local variable in its own initializer.
+ --> test.lox:2:11
+  |
+2 |   var a = a;
+  |       - variable declared here
+  |           ^
+"
+    );
+}
+
+#[test]
+fn format_check() {
+    let path = std::env::temp_dir().join("rlox-cli-fmt.lox");
+    fs::write(&path, "var a=1;\n").unwrap();
+    let check = || {
+        Command::new(env!("CARGO_BIN_EXE_rlox"))
+            .args(["fmt", "--check"])
+            .arg(&path)
+            .status()
+            .unwrap()
+            .code()
+    };
+    assert_eq!(check(), Some(1));
+    let status = Command::new(env!("CARGO_BIN_EXE_rlox"))
+        .arg("fmt")
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "var a = 1;\n");
+    assert_eq!(check(), Some(0));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn lint() {
+    let dir = std::env::temp_dir();
+    let config = dir.join("rlox-cli-lint.cfg");
+    fs::write(&config, "unused-variable = error\n").unwrap();
+    let (code, stderr) = rlox_with(
+        &["lint", "--config", config.to_str().unwrap()],
+        "lint",
+        "{\n  var a;\n}\n",
+    );
+    fs::remove_file(&config).unwrap();
+    assert_eq!(code, Some(1));
+    assert_eq!(
+        stderr,
+        "\
+error[unused-variable]: Unused variable 'a'.
+ --> test.lox:2:7
+  |
+2 |   var a;
+  |       ^
+"
+    );
+}
+
+#[test]
+fn lint_syntax_error() {
+    // There are no functions yet, so `return` is a syntax error.
+    let (code, stderr) = rlox_with(&["lint"], "lint-return", "print 1;\nreturn;\nprint 2;\n");
+    assert_eq!(code, Some(65));
+    assert_eq!(
+        stderr,
+        "\
+error: Expect expression.
+ --> test.lox:2:1
+  |
+2 | return;
+  | ^^^^^^
+"
+    );
+}
+
+#[test]
+fn rename() {
+    let path = std::env::temp_dir().join("rlox-cli-rename.lox");
+    fs::write(&path, "var a = 1;\n{\n  var b = 2;\n  print a;\n}\n").unwrap();
+    let rename = |position: &str, new_name: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rlox"))
+            .arg("rename")
+            .arg(&path)
+            .args([position, new_name])
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let stderr = stderr.replace(&path.display().to_string(), "test.lox");
+        (output.status.code(), stderr)
+    };
+    let (code, stderr) = rename("4:9", "b");
+    assert_eq!(code, Some(1));
+    assert_eq!(
+        stderr,
+        "\
+error: Renaming 'a' to 'b' would change which variable this refers to.
+ --> test.lox:4:9
+  |
+3 |   var b = 2;
+  |       - it would refer to this declaration instead
+4 |   print a;
+  |         ^
+"
+    );
+    assert_eq!(rename("4:9", "total"), (Some(0), String::new()));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "var total = 1;\n{\n  var b = 2;\n  print total;\n}\n"
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn highlight() {
+    let path = std::env::temp_dir().join("rlox-cli-highlight.lox");
+    fs::write(&path, "print 1;\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rlox"))
+        .arg("highlight")
+        .arg(&path)
+        .args(["--format", "html"])
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("<!DOCTYPE html>"));
+    assert!(stdout.contains(
+        "<pre class=\"lox\"><span class=\"keyword\">print</span> \
+         <span class=\"number\">1</span><span class=\"punctuation\">;</span>\n</pre>"
+    ));
+}
+
+#[test]
+fn doc() {
+    let dir = std::env::temp_dir().join("rlox-cli-doc");
+    let (src, out) = (dir.join("src"), dir.join("out"));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(src.join("lib")).unwrap();
+    fs::write(src.join("main.lox"), "import \"lib/a.lox\" as a;\n").unwrap();
+    fs::write(
+        src.join("lib/a.lox"),
+        "/// See [b].\nexport var a = 1;\nvar b;\n",
+    )
+    .unwrap();
+    fs::write(src.join("notes.txt"), "not a module").unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_rlox"))
+        .args(["doc", "--format", "markdown"])
+        .arg(&src)
+        .arg("-o")
+        .arg(&out)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let index = fs::read_to_string(out.join("index.md")).unwrap();
+    assert!(index.contains("- [`main.lox`](main.lox.md)\n"));
+    let main = fs::read_to_string(out.join("main.lox.md")).unwrap();
+    assert!(main.contains("- [`a`](lib/a.lox.md) from `lib/a.lox`"));
+    let module = fs::read_to_string(out.join("lib/a.lox.md")).unwrap();
+    assert!(module.contains("See [`b`](#b)."));
+    assert!(!out.join("notes.txt.md").exists());
+
+    fs::write(src.join("broken.lox"), "var").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rlox"))
+        .arg("doc")
+        .arg(&src)
+        .arg("-o")
+        .arg(&out)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(65));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Expect variable name."));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,259 @@
+//! Runs every `.lox` script under `tests/lox/` and checks it against the
+//! annotations in its comments, in the style of the Crafting Interpreters
+//! test suite:
+//!
+//! - `// expect: <text>` is a line the script prints, in order.
+//! - `// expect runtime error: <message>` is the error that stops the script,
+//!   raised on the line of the comment.
+//! - `// [line N] Error ...` is a compile error reported on line `N`, and
+//!   `// Error ...` one reported on the line of the comment itself.
+
+use rlox::{Formatter, Interpreter, LineIndex, Optimizer, Parser, Resolver, Scanner, SharedBuffer};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, PartialEq)]
+struct Outcome {
+    output: Vec<String>,
+    compile_errors: Vec<String>,
+    runtime_error: Option<String>,
+}
+
+fn expectations(source: &str) -> Outcome {
+    let mut expected = Outcome::default();
+    for (index, line) in source.lines().enumerate() {
+        if let Some(text) = annotation(line, "// expect:") {
+            expected.output.push(strip_space(text).to_string());
+        } else if let Some(message) = annotation(line, "// expect runtime error:") {
+            let message = strip_space(message);
+            expected.runtime_error = Some(format!("[line {}] {}", index + 1, message));
+        } else if let Some(error) = annotation(line, "// [line ") {
+            expected.compile_errors.push(format!("[line {}", error));
+        } else if let Some(error) = annotation(line, "// Error") {
+            expected
+                .compile_errors
+                .push(format!("[line {}] Error{}", index + 1, error));
+        }
+    }
+    expected
+}
+
+/// The rest of `line` after `marker`, if it contains the marker.
+fn annotation<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let start = line.find(marker)? + marker.len();
+    Some(&line[start..])
+}
+
+/// Drops the space after a `:` marker, which editors may have trimmed along
+/// with the rest of an empty expectation.
+fn strip_space(text: &str) -> &str {
+    text.strip_prefix(' ').unwrap_or(text)
+}
+
+/// Runs the script at `path`, relative to the crate, which its imports are
+/// relative to.
+/// Runs `source`, through an [`Optimizer`] first if `optimize` is set.
+fn run(path: &Path, source: &str, optimize: bool) -> Outcome {
+    let mut outcome = Outcome::default();
+    let lines = LineIndex::new(source);
+
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan().cloned().collect();
+    for error in scanner.errors() {
+        let line = lines.line(error.offset());
+        outcome
+            .compile_errors
+            .push(format!("[line {}] {}", line, error));
+    }
+    let statements = match Parser::new(tokens).parse() {
+        Ok(statements) => statements,
+        Err(errors) => {
+            for error in errors {
+                let line = lines.line(error.offset());
+                outcome
+                    .compile_errors
+                    .push(format!("[line {}] {}", line, error));
+            }
+            Vec::new()
+        }
+    };
+    if outcome.compile_errors.is_empty() {
+        if let Err(errors) = Resolver::new().resolve(&statements) {
+            for error in errors {
+                let line = lines.line(error.offset());
+                outcome
+                    .compile_errors
+                    .push(format!("[line {}] {}", line, error));
+            }
+        }
+    }
+    if !outcome.compile_errors.is_empty() {
+        return outcome;
+    }
+
+    let statements = if optimize {
+        let mut optimizer = Optimizer::default();
+        statements
+            .into_iter()
+            .map(|statement| optimizer.optimize(statement))
+            .collect()
+    } else {
+        statements
+    };
+    let output = SharedBuffer::new();
+    let mut interpreter = Interpreter::default();
+    interpreter.set_output(output.clone());
+    interpreter.set_source(source);
+    interpreter.set_module_path(&path.to_string_lossy());
+    for statement in statements {
+        if let Err(error) = interpreter.interpret(statement) {
+            let offset = error.trace().iter().find_map(|frame| frame.offset);
+            let line = offset.map_or(0, |offset| lines.line(offset));
+            outcome.runtime_error = Some(format!("[line {}] {}", line, error));
+            break;
+        }
+    }
+    outcome.output = output.contents().lines().map(str::to_string).collect();
+    outcome
+}
+
+/// A line-by-line comparison, marking expected lines with `-` and actual
+/// lines with `+` where they differ.
+fn diff(section: &str, expected: &[String], actual: &[String]) -> String {
+    let mut diff = String::new();
+    if expected == actual {
+        return diff;
+    }
+    diff.push_str(&format!("  {}:\n", section));
+    for index in 0..expected.len().max(actual.len()) {
+        match (expected.get(index), actual.get(index)) {
+            (Some(expected), Some(actual)) if expected == actual => {
+                diff.push_str(&format!("      {}\n", expected));
+            }
+            (expected, actual) => {
+                if let Some(expected) = expected {
+                    diff.push_str(&format!("    - {}\n", expected));
+                }
+                if let Some(actual) = actual {
+                    diff.push_str(&format!("    + {}\n", actual));
+                }
+            }
+        }
+    }
+    diff
+}
+
+fn check(path: &Path) -> Result<(), String> {
+    let source = fs::read_to_string(path).map_err(|err| format!("  {}\n", err))?;
+    let expected = expectations(&source);
+    let actual = run(relative(path), &source, false);
+    if expected == actual {
+        return Ok(());
+    }
+    let mut report = diff("output", &expected.output, &actual.output);
+    report += &diff(
+        "compile errors",
+        &expected.compile_errors,
+        &actual.compile_errors,
+    );
+    report += &diff(
+        "runtime error",
+        &expected.runtime_error.into_iter().collect::<Vec<_>>(),
+        &actual.runtime_error.into_iter().collect::<Vec<_>>(),
+    );
+    Err(report)
+}
+
+/// `path` relative to the crate, which tests run in, so that paths in
+/// error messages are the same on every machine.
+fn relative(path: &Path) -> &Path {
+    path.strip_prefix(env!("CARGO_MANIFEST_DIR")).unwrap()
+}
+
+fn lox_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            lox_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "lox") {
+            files.push(path);
+        }
+    }
+}
+
+/// Every script that compiles must format to a fixed point and behave the
+/// same afterwards.
+#[test]
+fn formatting() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lox");
+    let mut files = Vec::new();
+    lox_files(&root, &mut files);
+    let formatter = Formatter::new();
+    for path in &files {
+        let source = fs::read_to_string(path).unwrap();
+        let formatted = match formatter.format(&source) {
+            Ok(formatted) => formatted,
+            Err(_) => continue,
+        };
+        let name = path.display();
+        assert_eq!(
+            formatter.format(&formatted).as_ref(),
+            Ok(&formatted),
+            "{} does not format to a fixed point",
+            name
+        );
+        let path = relative(path);
+        let (before, after) = (run(path, &source, false), run(path, &formatted, false));
+        assert_eq!(before.output, after.output, "{} output changed", name);
+        assert_eq!(before.compile_errors, after.compile_errors, "{}", name);
+        let message = |outcome: Outcome| {
+            outcome
+                .runtime_error
+                .map(|e| e.split_once("] ").unwrap().1.to_string())
+        };
+        assert_eq!(message(before), message(after), "{} error changed", name);
+    }
+}
+
+/// Folding constants and dead branches must not change what any script
+/// prints or which error it stops with.
+#[test]
+fn optimizing() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lox");
+    let mut files = Vec::new();
+    lox_files(&root, &mut files);
+    for path in &files {
+        let source = fs::read_to_string(path).unwrap();
+        let name = path.display();
+        let path = relative(path);
+        let (unfolded, folded) = (run(path, &source, false), run(path, &source, true));
+        assert_eq!(unfolded.output, folded.output, "{} output changed", name);
+        assert_eq!(
+            unfolded.runtime_error, folded.runtime_error,
+            "{} error changed",
+            name
+        );
+    }
+}
+
+#[test]
+fn conformance() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lox");
+    let mut files = Vec::new();
+    lox_files(&root, &mut files);
+    files.sort();
+
+    let mut failed = 0;
+    for path in &files {
+        let name = path.strip_prefix(&root).unwrap().display();
+        match check(path) {
+            Ok(()) => println!("PASS {}", name),
+            Err(report) => {
+                failed += 1;
+                println!("FAIL {}\n{}", name, report);
+            }
+        }
+    }
+    assert!(!files.is_empty(), "no tests found in {}", root.display());
+    assert_eq!(failed, 0, "{} of {} scripts failed", failed, files.len());
+}